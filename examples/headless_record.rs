@@ -0,0 +1,30 @@
+use gravity_sim::object::{Object, ObjectPool};
+use gravity_sim::physics::PhysicsHandler;
+use gravity_sim::recorder::Recorder;
+use macroquad::prelude::*;
+
+const STEPS: u32 = 10_000;
+const SAMPLE_INTERVAL: u64 = 10;
+const MAX_ROWS_PER_FILE: usize = 100_000;
+
+fn main() -> std::io::Result<()> {
+    let mut objects = ObjectPool::new();
+    objects.push(Object::new(Vec3::ZERO, Vec3::ZERO, 100., 3., YELLOW));
+    objects.push(Object::new(
+        vec3(20., 0., 0.),
+        vec3(0., 0., 2.2),
+        1.,
+        1.,
+        BLUE,
+    ));
+
+    let mut physics_handler = PhysicsHandler::default();
+    let mut recorder = Recorder::new("recordings", "headless", SAMPLE_INTERVAL, MAX_ROWS_PER_FILE);
+
+    for _ in 0..STEPS {
+        physics_handler.step(&mut objects);
+        recorder.sample(&physics_handler, &objects)?;
+    }
+
+    recorder.flush()
+}
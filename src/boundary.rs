@@ -0,0 +1,296 @@
+use crate::object::{Object, ObjectPool};
+use crate::units::{Quantity, Units};
+use macroquad::prelude::*;
+use std::collections::VecDeque;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoundaryShape {
+    Sphere { center: Vec3, radius: f32 },
+    Box { min: Vec3, max: Vec3 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoundaryPolicy {
+    Despawn,
+    Reflect,
+    Wrap,
+    Freeze,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoundaryEvent {
+    Despawned(usize),
+    Reflected(usize),
+    Wrapped(usize),
+    Frozen(usize),
+    Escaped(usize),
+}
+
+impl BoundaryEvent {
+    pub fn id(&self) -> usize {
+        match *self {
+            BoundaryEvent::Despawned(id)
+            | BoundaryEvent::Reflected(id)
+            | BoundaryEvent::Wrapped(id)
+            | BoundaryEvent::Frozen(id)
+            | BoundaryEvent::Escaped(id) => id,
+        }
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            BoundaryEvent::Despawned(_) => "left the domain",
+            BoundaryEvent::Reflected(_) => "bounced off a boundary",
+            BoundaryEvent::Wrapped(_) => "wrapped around a boundary",
+            BoundaryEvent::Frozen(_) => "froze at a boundary",
+            BoundaryEvent::Escaped(_) => "escaped",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BoundaryLog {
+    entries: VecDeque<(f32, BoundaryEvent)>,
+    capacity: usize,
+}
+
+impl BoundaryLog {
+    pub fn new(capacity: usize) -> Self {
+        BoundaryLog {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn record(&mut self, time: f32, events: &[BoundaryEvent]) {
+        for event in events {
+            self.entries.retain(|(_, listed)| listed != event);
+            if self.entries.len() >= self.capacity {
+                self.entries.pop_front();
+            }
+            self.entries.push_back((time, *event));
+        }
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &(f32, BoundaryEvent)> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn draw(&self, units: &Units, x: f32, y: f32, font_size: f32, color: Color) {
+        for (i, (time, event)) in self.entries.iter().rev().enumerate() {
+            draw_text(
+                &format!(
+                    "Object {} {} at {}",
+                    event.id(),
+                    event.describe(),
+                    units.format(Quantity::Time, *time)
+                ),
+                x,
+                y + i as f32 * font_size,
+                font_size,
+                color,
+            );
+        }
+    }
+}
+
+impl Default for BoundaryLog {
+    fn default() -> Self {
+        BoundaryLog::new(8)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Boundary {
+    pub shape: BoundaryShape,
+    pub policy: BoundaryPolicy,
+}
+
+impl Boundary {
+    pub fn new(shape: BoundaryShape, policy: BoundaryPolicy) -> Self {
+        Boundary { shape, policy }
+    }
+
+    pub fn contains(&self, pos: Vec3) -> bool {
+        match self.shape {
+            BoundaryShape::Sphere { center, radius } => (pos - center).length() <= radius,
+            BoundaryShape::Box { min, max } => pos.cmpge(min).all() && pos.cmple(max).all(),
+        }
+    }
+
+    pub fn apply(&self, obj: &mut Object) -> Option<BoundaryEvent> {
+        if self.contains(obj.position) {
+            return None;
+        }
+
+        match self.policy {
+            BoundaryPolicy::Despawn => return Some(BoundaryEvent::Despawned(obj.id)),
+            BoundaryPolicy::Reflect => self.reflect(obj),
+            BoundaryPolicy::Wrap => self.wrap(obj),
+            BoundaryPolicy::Freeze => {
+                obj.position = self.clamp(obj.position);
+                obj.velocity = Vec3::ZERO;
+                obj.frozen = true;
+            }
+        }
+
+        Some(match self.policy {
+            BoundaryPolicy::Reflect => BoundaryEvent::Reflected(obj.id),
+            BoundaryPolicy::Wrap => BoundaryEvent::Wrapped(obj.id),
+            _ => BoundaryEvent::Frozen(obj.id),
+        })
+    }
+
+    fn clamp(&self, pos: Vec3) -> Vec3 {
+        match self.shape {
+            BoundaryShape::Sphere { center, radius } => {
+                center + (pos - center).clamp_length_max(radius)
+            }
+            BoundaryShape::Box { min, max } => pos.clamp(min, max),
+        }
+    }
+
+    fn reflect(&self, obj: &mut Object) {
+        match self.shape {
+            BoundaryShape::Sphere { center, .. } => {
+                let normal = (obj.position - center).normalize_or_zero();
+                let outward = obj.velocity.dot(normal);
+                if outward > 0. {
+                    obj.velocity -= 2. * outward * normal;
+                }
+            }
+            BoundaryShape::Box { min, max } => {
+                for axis in 0..3 {
+                    if (obj.position[axis] < min[axis] && obj.velocity[axis] < 0.)
+                        || (obj.position[axis] > max[axis] && obj.velocity[axis] > 0.)
+                    {
+                        obj.velocity[axis] = -obj.velocity[axis];
+                    }
+                }
+            }
+        }
+
+        obj.position = self.clamp(obj.position);
+    }
+
+    fn wrap(&self, obj: &mut Object) {
+        match self.shape {
+            BoundaryShape::Sphere { center, radius } => {
+                obj.position = center - (obj.position - center).normalize_or_zero() * radius;
+            }
+            BoundaryShape::Box { min, max } => {
+                let size = max - min;
+                for axis in 0..3 {
+                    if size[axis] > 0. {
+                        obj.position[axis] =
+                            min[axis] + (obj.position[axis] - min[axis]).rem_euclid(size[axis]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Domain {
+    pub boundaries: Vec<Boundary>,
+    pub escape_distance: Option<f32>,
+    pub despawn_escaped: bool,
+}
+
+impl Domain {
+    pub fn new(
+        boundaries: Vec<Boundary>,
+        escape_distance: Option<f32>,
+        despawn_escaped: bool,
+    ) -> Self {
+        Domain {
+            boundaries,
+            escape_distance,
+            despawn_escaped,
+        }
+    }
+
+    pub fn contains(&self, pos: Vec3) -> bool {
+        self.boundaries
+            .iter()
+            .all(|boundary| boundary.contains(pos))
+    }
+
+    pub fn despawns(&self, pos: Vec3) -> bool {
+        self.boundaries
+            .iter()
+            .any(|boundary| boundary.policy == BoundaryPolicy::Despawn && !boundary.contains(pos))
+    }
+
+    pub fn apply(&self, objects: &mut ObjectPool, grav_const: f32) -> Vec<BoundaryEvent> {
+        let mut events = Vec::new();
+
+        for obj in objects.iter_mut() {
+            for boundary in &self.boundaries {
+                if let Some(event) = boundary.apply(obj) {
+                    events.push(event);
+                    break;
+                }
+            }
+        }
+
+        if let Some(distance) = self.escape_distance {
+            events.extend(
+                Self::find_escaped(objects, grav_const, distance)
+                    .into_iter()
+                    .map(BoundaryEvent::Escaped),
+            );
+        }
+
+        for event in &events {
+            match event {
+                BoundaryEvent::Despawned(id) => objects.remove(*id),
+                BoundaryEvent::Escaped(id) if self.despawn_escaped => objects.remove(*id),
+                _ => {}
+            }
+        }
+
+        events
+    }
+
+    pub fn find_escaped(objects: &ObjectPool, grav_const: f32, distance: f32) -> Vec<usize> {
+        let total_mass: f32 = objects.iter().map(|obj| obj.mass).sum();
+        let total_pos: Vec3 = objects.iter().map(|obj| obj.mass * obj.position).sum();
+        let total_momentum: Vec3 = objects.iter().map(|obj| obj.mass * obj.velocity).sum();
+
+        objects
+            .iter()
+            .filter(|obj| {
+                let rest_mass = total_mass - obj.mass;
+                if rest_mass <= 0. {
+                    return false;
+                }
+
+                let rest_pos = (total_pos - obj.mass * obj.position) / rest_mass;
+                let rest_veloc = (total_momentum - obj.mass * obj.velocity) / rest_mass;
+                let dist = (obj.position - rest_pos).length();
+                if dist <= distance {
+                    return false;
+                }
+
+                let energy = 0.5 * (obj.velocity - rest_veloc).length_squared()
+                    - grav_const * rest_mass / dist;
+                energy > 0.
+            })
+            .map(|obj| obj.id)
+            .collect()
+    }
+}
@@ -1,9 +1,26 @@
-use crate::object::{Object, ObjectPool, Trajectory};
+use crate::edit::{EditCommand, EditHistory};
+use crate::encounter::{Encounter, closest_approach, find_encounters};
+use crate::export::{self, ExportFormat};
+use crate::frame::{FrameTransform, RotatingFrame};
+use crate::influence::InfluenceTracker;
+use crate::maneuver::ManeuverNode;
+use crate::object::{Object, ObjectPool, Termination, Trajectory};
+use crate::orbit::{LagrangeSystem, OrbitalElements, dominant_body};
 use crate::physics::PhysicsHandler;
+use crate::prediction::{AdaptiveSampling, TrajectoryPredictor};
 use crate::renderer::Renderer;
+use crate::scenario::{Scenario, random_color};
+#[cfg(feature = "scene")]
+use crate::scene::Scene;
 use crate::screen::*;
+use crate::spawn::SpawnTool;
+use crate::timeline::Timeline;
+use crate::units::{Quantity, Units};
 use macroquad::prelude::*;
-use std::collections::HashMap;
+
+const PREDICTION_POINTS: u32 = 10_000;
+const PREDICTION_STEP: u32 = 2;
+const PREDICTION_TOLERANCE: f32 = 0.05;
 
 pub struct ControlHandler {
     move_speed: f32,
@@ -11,7 +28,37 @@ pub struct ControlHandler {
     place_elevation: f32,
     control_state: ControlState,
     ghost_obj: Option<Object>,
-    trajectories: HashMap<usize, Trajectory>,
+    predictor: TrajectoryPredictor,
+    drag_predictor: TrajectoryPredictor,
+    scene_path: String,
+    edit_history: EditHistory,
+    scenario_menu_open: bool,
+    active_group: Option<String>,
+    group_count: usize,
+    group_edit: Option<Vec<Object>>,
+    discontinuity: bool,
+    camera_tracking: CameraTracking,
+    rotating_frame: Option<RotatingFrame>,
+    frame_transform: Option<FrameTransform>,
+    frame_pick: Option<usize>,
+    trajectory_reference: Option<usize>,
+    spawn_tool: SpawnTool,
+    spawn_center: Option<usize>,
+    spawn_preview: Vec<Object>,
+    encounter_pair: Option<(usize, usize)>,
+    encounter_pick: Option<usize>,
+    encounters: Vec<Encounter>,
+    show_impacts: bool,
+    impacts: Vec<(usize, Termination)>,
+    orbit: Option<OrbitReadout>,
+    export_dir: String,
+    lagrange_pair: Option<(usize, usize)>,
+    lagrange_pick: Option<usize>,
+    lagrange: Option<LagrangeSystem>,
+    maneuver_node: Option<ManeuverNode>,
+    maneuver_predictor: TrajectoryPredictor,
+    hovered: Option<usize>,
+    moving: Option<(usize, Vec3)>,
 }
 
 impl ControlHandler {
@@ -22,31 +69,100 @@ impl ControlHandler {
             place_elevation: 0.0,
             control_state: ControlState::Idle,
             ghost_obj: None,
-            trajectories: HashMap::new(),
+            predictor: Self::predictor(),
+            drag_predictor: Self::predictor(),
+            scene_path: String::from("scene.ron"),
+            edit_history: EditHistory::default(),
+            scenario_menu_open: false,
+            active_group: None,
+            group_count: 0,
+            group_edit: None,
+            discontinuity: false,
+            camera_tracking: CameraTracking::Off,
+            rotating_frame: None,
+            frame_transform: None,
+            frame_pick: None,
+            trajectory_reference: None,
+            spawn_tool: SpawnTool::default(),
+            spawn_center: None,
+            spawn_preview: Vec::new(),
+            encounter_pair: None,
+            encounter_pick: None,
+            encounters: Vec::new(),
+            show_impacts: false,
+            impacts: Vec::new(),
+            orbit: None,
+            export_dir: String::from("exports"),
+            lagrange_pair: None,
+            lagrange_pick: None,
+            lagrange: None,
+            maneuver_node: None,
+            maneuver_predictor: Self::predictor(),
+            hovered: None,
+            moving: None,
         }
     }
 
+    fn predictor() -> TrajectoryPredictor {
+        TrajectoryPredictor::new(PREDICTION_POINTS, PREDICTION_STEP)
+            .with_background(true)
+            .with_sampling(Some(AdaptiveSampling::new(
+                PREDICTION_TOLERANCE,
+                PREDICTION_POINTS as usize,
+            )))
+    }
+
     pub fn handle_input(
         &mut self,
         renderer: &mut Renderer,
         objects: &mut ObjectPool,
-        physics_handler: &PhysicsHandler,
+        physics_handler: &mut PhysicsHandler,
+        timeline: &mut Timeline,
+        units: &mut Units,
         dt: f32,
     ) {
-        self.handle_movement(renderer, dt);
+        self.frame_transform = self.get_frame_transform(objects);
+        self.orbit = None;
+        self.hovered = None;
+
+        if is_key_down(KeyCode::LeftAlt) {
+            self.handle_group(renderer, objects, dt);
+        } else {
+            self.finish_group_edit(objects);
+            if !Self::is_chord_active() && !Self::is_scrubbing(timeline) {
+                self.handle_movement(renderer, dt);
+            }
+        }
         self.handle_ghost_obj(dt);
+        self.handle_frame(objects, physics_handler);
+        self.handle_rotating_frame(renderer, objects, physics_handler);
+        self.handle_trajectory_reference(renderer, objects);
+        self.handle_encounters(renderer, objects, physics_handler);
+        self.handle_impacts(objects, physics_handler);
+        self.handle_lagrange(renderer, objects, physics_handler);
+        self.handle_undo(objects);
+        self.handle_timeline(timeline, objects, physics_handler);
+        self.handle_export(objects, physics_handler, timeline);
+        self.handle_scenario_menu(objects, physics_handler, timeline);
+        #[cfg(feature = "scene")]
+        self.handle_scene(renderer, objects, physics_handler, timeline, units);
+        #[cfg(not(feature = "scene"))]
+        let _ = units;
 
         self.control_state = match self.control_state {
             ControlState::Idle => self.handle_idle(renderer, physics_handler, objects),
             ControlState::Place => self.handle_place(renderer),
             ControlState::Drag => self.handle_drag(renderer, physics_handler, objects),
+            ControlState::Spawn => self.handle_spawn(renderer, physics_handler, objects),
+            ControlState::Move => self.handle_move(renderer, objects),
+            ControlState::Maneuver => self.handle_maneuver(renderer, physics_handler, objects, dt),
         };
     }
 
     fn handle_idle(
         &mut self,
         renderer: &mut Renderer,
-        physics_handler: &PhysicsHandler,
+        physics_handler: &mut PhysicsHandler,
         objects: &mut ObjectPool,
     ) -> ControlState {
         if let Some(obj) = self.get_hovered_obj(renderer, objects) {
@@ -57,14 +173,86 @@ impl ControlHandler {
 
             renderer.draw_halo(obj.position, obj.radius * 1.1, Some(color));
             self.draw_obj_trajectory(physics_handler, renderer, objects, &obj);
+
+            if let Some(obj) = objects.get(obj.id).cloned() {
+                self.update_orbit(renderer, physics_handler, objects, &obj);
+            }
+
+            self.hovered = Some(obj.id);
         }
 
-        if is_key_released(KeyCode::R) {
-            if let Some(obj) = self.get_hovered_obj(renderer, objects) {
-                objects.remove(obj.id);
+        if is_key_released(KeyCode::R)
+            && !is_key_down(KeyCode::LeftAlt)
+            && let Some(obj) = self.get_hovered_obj(renderer, objects)
+        {
+            self.edit_history.delete(objects, obj.id);
+        }
+
+        if is_key_released(KeyCode::G)
+            && let Some(obj) = self.get_hovered_obj(renderer, objects)
+        {
+            let group = self.active_group.get_or_insert_with(|| {
+                self.group_count += 1;
+                format!("group {}", self.group_count)
+            });
+            let group = group.clone();
+            self.edit_history
+                .execute(objects, EditCommand::Join { group, id: obj.id });
+        }
+
+        if is_key_released(KeyCode::H) {
+            self.active_group = None;
+        }
+
+        if !is_key_down(KeyCode::LeftAlt)
+            && let Some(obj) = self.get_hovered_obj(renderer, objects)
+            && let Some(before) = objects.get(obj.id).filter(|obj| !obj.test_particle)
+        {
+            let factor = if is_key_pressed(KeyCode::Up) {
+                1.25
+            } else if is_key_pressed(KeyCode::Down) {
+                0.8
+            } else {
+                1.
+            };
+
+            if factor != 1. {
+                let mut after = before.clone();
+                after.mass *= factor;
+                after.radius *= f32::cbrt(factor);
+                let before = before.clone();
+                self.edit_history
+                    .execute(objects, EditCommand::Edit { before, after });
             }
         }
 
+        if is_mouse_button_pressed(MouseButton::Right)
+            && let Some(obj) = self.get_hovered_obj(renderer, objects)
+            && let Some(world_obj) = objects.get(obj.id)
+        {
+            self.moving = Some((obj.id, world_obj.position));
+            return ControlState::Move;
+        }
+
+        if is_key_released(KeyCode::B)
+            && let Some(obj) = self.get_hovered_obj(renderer, objects)
+        {
+            self.spawn_center = Some(obj.id);
+            self.spawn_tool.color = random_color();
+            self.spawn_preview.clear();
+            return ControlState::Spawn;
+        }
+
+        if is_key_released(KeyCode::M)
+            && let Some(obj) = self.get_hovered_obj(renderer, objects)
+        {
+            let lead = Self::prediction_horizon(physics_handler) * 0.05;
+            let node = ManeuverNode::new(obj.id, physics_handler.get_sim_time() + lead);
+            physics_handler.add_maneuver(node);
+            self.maneuver_node = Some(node);
+            return ControlState::Maneuver;
+        }
+
         if is_mouse_button_released(MouseButton::Left) {
             return ControlState::Place;
         }
@@ -79,7 +267,7 @@ impl ControlHandler {
                 Vec3::ZERO,
                 1.0,
                 1.0,
-                Self::random_color(),
+                random_color(),
             ));
         }
 
@@ -124,8 +312,14 @@ impl ControlHandler {
 
             obj.draw(renderer);
 
+            let virtual_obj = self
+                .frame_transform
+                .map_or(virtual_obj.clone(), |transform| {
+                    transform.object_to_world(&virtual_obj)
+                });
+            self.update_orbit(renderer, physics_handler, objects, &virtual_obj);
             let mut clones = objects.clone().get_all_in_area(virtual_obj.position, 500.0);
-            clones.push(virtual_obj.clone());
+            clones.push(virtual_obj);
 
             self.draw_objects_trajectories(physics_handler, renderer, &clones);
         }
@@ -134,7 +328,10 @@ impl ControlHandler {
             if let Some(obj) = &mut self.ghost_obj {
                 let veloc = (ray.plane_intersect(Some(self.place_elevation)) - obj.position) / 10.0;
                 obj.add_velocity(veloc);
-                objects.push(obj.clone());
+                let obj = self
+                    .frame_transform
+                    .map_or(obj.clone(), |transform| transform.object_to_world(obj));
+                self.edit_history.spawn(objects, obj);
                 self.ghost_obj = None;
             }
 
@@ -149,13 +346,1334 @@ impl ControlHandler {
         ControlState::Drag
     }
 
+    fn handle_spawn(
+        &mut self,
+        renderer: &mut Renderer,
+        physics_handler: &PhysicsHandler,
+        objects: &mut ObjectPool,
+    ) -> ControlState {
+        let Some(center) = self.spawn_center.and_then(|id| objects.get(id)).cloned() else {
+            self.spawn_preview.clear();
+            return ControlState::Idle;
+        };
+
+        let tool = &mut self.spawn_tool;
+        let mut changed = self.spawn_preview.is_empty();
+
+        if is_key_released(KeyCode::B) {
+            tool.shape = tool.shape.next();
+            changed = true;
+        }
+        if is_key_released(KeyCode::M) {
+            tool.velocity = tool.velocity.next();
+            changed = true;
+        }
+        if is_key_released(KeyCode::X) {
+            tool.mass_range = if tool.spawns_particles() {
+                (0.05, 0.5)
+            } else {
+                (0., 0.)
+            };
+            changed = true;
+        }
+        if is_key_pressed(KeyCode::Up) && tool.count < 4096 {
+            tool.count *= 2;
+            changed = true;
+        }
+        if is_key_pressed(KeyCode::Down) && tool.count > 1 {
+            tool.count /= 2;
+            changed = true;
+        }
+        if is_key_pressed(KeyCode::RightBracket) {
+            tool.radius_range = (tool.radius_range.0 * 1.25, tool.radius_range.1 * 1.25);
+            changed = true;
+        }
+        if is_key_pressed(KeyCode::LeftBracket) {
+            tool.radius_range = (tool.radius_range.0 * 0.8, tool.radius_range.1 * 0.8);
+            changed = true;
+        }
+        if is_key_pressed(KeyCode::Equal) {
+            tool.radius_range.0 = (tool.radius_range.0 * 1.25).min(tool.radius_range.1);
+            changed = true;
+        }
+        if is_key_pressed(KeyCode::Minus) {
+            tool.radius_range.0 *= 0.8;
+            changed = true;
+        }
+
+        if changed {
+            let anchor = Object {
+                position: Vec3::ZERO,
+                velocity: Vec3::ZERO,
+                ..center.clone()
+            };
+            self.spawn_preview = tool.generate(&anchor, physics_handler.get_grav_const());
+        }
+
+        let anchored = self.spawn_preview.iter().map(|obj| {
+            let mut obj = obj.clone();
+            obj.position += center.position;
+            obj.velocity += center.velocity;
+            obj
+        });
+
+        if is_key_released(KeyCode::Enter) {
+            self.edit_history.spawn_all(objects, anchored.collect());
+            self.spawn_preview.clear();
+            self.spawn_center = None;
+            return ControlState::Idle;
+        }
+
+        for obj in anchored {
+            let obj = self.to_display(&obj);
+            renderer.draw_point(
+                obj.position,
+                obj.radius,
+                Some(Color {
+                    a: 0.5,
+                    ..obj.color
+                }),
+            );
+        }
+
+        let center = self.to_display(&center);
+        renderer.draw_halo(
+            center.position,
+            center.radius * 1.3,
+            Some(Color { a: 0.15, ..GREEN }),
+        );
+
+        if is_key_released(KeyCode::Escape) {
+            self.spawn_preview.clear();
+            self.spawn_center = None;
+            return ControlState::Idle;
+        }
+
+        ControlState::Spawn
+    }
+
+    fn handle_maneuver(
+        &mut self,
+        renderer: &mut Renderer,
+        physics_handler: &mut PhysicsHandler,
+        objects: &mut ObjectPool,
+        dt: f32,
+    ) -> ControlState {
+        let Some(edited) = self.maneuver_node else {
+            return ControlState::Idle;
+        };
+
+        if objects.get(edited.object).is_none() {
+            physics_handler.remove_maneuver(&edited);
+            self.maneuver_node = None;
+            return ControlState::Idle;
+        }
+
+        let now = physics_handler.get_sim_time();
+        if edited.time < now {
+            self.maneuver_node = None;
+            return ControlState::Idle;
+        }
+
+        let boost = if is_key_down(KeyCode::LeftShift) {
+            10.
+        } else {
+            1.
+        };
+        let time_rate = Self::prediction_horizon(physics_handler) * 0.1 * boost * dt;
+        let dv_rate = self.scale_speed * 0.1 * boost * dt;
+
+        let mut node = edited;
+        if is_key_down(KeyCode::RightBracket) {
+            node.time += time_rate;
+        }
+        if is_key_down(KeyCode::LeftBracket) {
+            node.time -= time_rate;
+        }
+        node.time = node.time.max(now);
+        for (up, down, component) in [
+            (KeyCode::Up, KeyCode::Down, &mut node.prograde),
+            (KeyCode::PageUp, KeyCode::PageDown, &mut node.normal),
+            (KeyCode::Home, KeyCode::End, &mut node.radial),
+        ] {
+            if is_key_down(up) {
+                *component += dv_rate;
+            }
+            if is_key_down(down) {
+                *component -= dv_rate;
+            }
+        }
+        if node != edited {
+            physics_handler.update_maneuver(&edited, node);
+        }
+        self.maneuver_node = Some(node);
+
+        if is_key_released(KeyCode::Enter) {
+            self.maneuver_node = None;
+            return ControlState::Idle;
+        }
+
+        if is_key_released(KeyCode::Delete) {
+            physics_handler.remove_maneuvers(node.object);
+            physics_handler.add_maneuver(node);
+        }
+
+        if is_key_released(KeyCode::Escape) {
+            physics_handler.remove_maneuver(&node);
+            self.maneuver_node = None;
+            return ControlState::Idle;
+        }
+
+        self.maneuver_predictor.update(
+            objects,
+            physics_handler,
+            self.rotating_frame.as_ref(),
+            self.trajectory_reference,
+        );
+        let planned: Vec<ManeuverNode> = physics_handler
+            .get_maneuvers()
+            .iter()
+            .filter(|planned| planned.object == node.object)
+            .copied()
+            .collect();
+
+        if let Some(obj) = objects.get(node.object).cloned()
+            && let Some(traj) = self.maneuver_predictor.get(obj.id)
+        {
+            let origin = self.trajectory_origin(objects, traj);
+            traj.draw_from(renderer, origin, Some(obj.color), obj.radius);
+
+            let display = self.to_display(&obj);
+            renderer.draw_halo(
+                display.position,
+                display.radius * 1.3,
+                Some(Color { a: 0.15, ..SKYBLUE }),
+            );
+
+            for planned_node in &planned {
+                if let Some(point) = traj.sample_at(planned_node.time) {
+                    let position = origin + point;
+                    let color = if *planned_node == node { SKYBLUE } else { GRAY };
+                    renderer.draw_halo(position, obj.radius * 0.8, Some(color));
+
+                    if let Some(delta_v) = planned_node.delta_v(objects) {
+                        let direction =
+                            self.to_display_point(delta_v) - self.to_display_point(Vec3::ZERO);
+                        renderer.draw_arrow(position, position + direction * 20., Some(color));
+                    }
+                }
+            }
+        }
+
+        ControlState::Maneuver
+    }
+
+    fn prediction_horizon(physics_handler: &PhysicsHandler) -> f32 {
+        (PREDICTION_POINTS * PREDICTION_STEP) as f32 * physics_handler.get_timestep()
+    }
+
+    pub fn get_maneuver_node(&self) -> Option<&ManeuverNode> {
+        self.maneuver_node.as_ref()
+    }
+
+    pub fn draw_maneuver(
+        &self,
+        units: &Units,
+        sim_time: f32,
+        x: f32,
+        y: f32,
+        font_size: f32,
+        color: Color,
+    ) {
+        let Some(node) = self.maneuver_node else {
+            return;
+        };
+
+        let lines = [
+            format!(
+                "Maneuver on Object {} [Enter to commit, Esc to cancel, Del to clear]",
+                node.object
+            ),
+            format!(
+                "[[/]] burn in: {}",
+                units.format(Quantity::Time, node.time - sim_time)
+            ),
+            format!(
+                "[Up/Down] prograde: {}",
+                units.format(Quantity::Velocity, node.prograde)
+            ),
+            format!(
+                "[PgUp/PgDn] normal: {}",
+                units.format(Quantity::Velocity, node.normal)
+            ),
+            format!(
+                "[Home/End] radial: {}",
+                units.format(Quantity::Velocity, node.radial)
+            ),
+            format!(
+                "delta-v: {}",
+                units.format(Quantity::Velocity, node.magnitude())
+            ),
+        ];
+
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(line, x, y + i as f32 * font_size, font_size, color);
+        }
+    }
+
+    fn handle_move(&mut self, renderer: &mut Renderer, objects: &mut ObjectPool) -> ControlState {
+        let Some((id, from)) = self.moving else {
+            return ControlState::Idle;
+        };
+        let Some(obj) = objects.get(id).map(|obj| self.to_display(obj)) else {
+            self.moving = None;
+            return ControlState::Idle;
+        };
+
+        let ray = Ray::new_from_mouse(renderer.get_cam());
+        let target = ray.plane_intersect(Some(obj.position.y));
+        let target = self
+            .frame_transform
+            .map_or(target, |transform| transform.point_to_world(target));
+        if let Some(obj) = objects.get_mut(id)
+            && obj.position != target
+        {
+            obj.position = target;
+            objects.mark_edited();
+        }
+
+        if is_mouse_button_released(MouseButton::Right) {
+            self.edit_history.record(EditCommand::Move {
+                id,
+                from,
+                to: target,
+            });
+            self.moving = None;
+            return ControlState::Idle;
+        }
+
+        if is_key_released(KeyCode::Escape) {
+            if let Some(obj) = objects.get_mut(id) {
+                obj.position = from;
+                objects.mark_edited();
+            }
+            self.moving = None;
+            return ControlState::Idle;
+        }
+
+        ControlState::Move
+    }
+
+    pub fn get_spawn_tool(&mut self) -> &mut SpawnTool {
+        &mut self.spawn_tool
+    }
+
+    pub fn get_edit_history(&mut self) -> &mut EditHistory {
+        &mut self.edit_history
+    }
+
+    fn handle_undo(&mut self, objects: &mut ObjectPool) {
+        if !is_key_down(KeyCode::LeftControl) && !is_key_down(KeyCode::RightControl) {
+            return;
+        }
+
+        if is_key_pressed(KeyCode::Z) {
+            self.edit_history.undo(objects);
+        }
+        if is_key_pressed(KeyCode::Y) {
+            self.edit_history.redo(objects);
+        }
+    }
+
+    fn handle_timeline(
+        &mut self,
+        timeline: &mut Timeline,
+        objects: &mut ObjectPool,
+        physics_handler: &mut PhysicsHandler,
+    ) {
+        if is_key_pressed(KeyCode::P) {
+            if timeline.is_paused() {
+                self.discontinuity |= timeline.resume(objects, physics_handler);
+            } else {
+                timeline.pause(physics_handler, objects);
+            }
+        }
+
+        let scrub_step = if is_key_down(KeyCode::LeftShift) {
+            10
+        } else {
+            1
+        };
+        if is_key_pressed(KeyCode::Left) {
+            self.discontinuity |= timeline.scrub(-scrub_step, objects, physics_handler);
+        }
+        if is_key_pressed(KeyCode::Right) {
+            self.discontinuity |= timeline.scrub(scrub_step, objects, physics_handler);
+        }
+    }
+
+    pub fn take_discontinuity(&mut self) -> bool {
+        std::mem::take(&mut self.discontinuity)
+    }
+
+    fn handle_scenario_menu(
+        &mut self,
+        objects: &mut ObjectPool,
+        physics_handler: &mut PhysicsHandler,
+        timeline: &mut Timeline,
+    ) {
+        if is_key_pressed(KeyCode::Tab) {
+            self.scenario_menu_open = !self.scenario_menu_open;
+        }
+
+        if !self.scenario_menu_open {
+            return;
+        }
+
+        const KEYS: [KeyCode; 7] = [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+            KeyCode::Key6,
+            KeyCode::Key7,
+        ];
+
+        for (key, scenario) in KEYS.iter().zip(Scenario::ALL) {
+            if is_key_pressed(*key) {
+                self.load_scenario(scenario, objects, physics_handler, timeline);
+                self.scenario_menu_open = false;
+            }
+        }
+    }
+
+    pub fn load_scenario(
+        &mut self,
+        scenario: Scenario,
+        objects: &mut ObjectPool,
+        physics_handler: &mut PhysicsHandler,
+        timeline: &mut Timeline,
+    ) {
+        *objects = scenario.generate(physics_handler.get_grav_const());
+        self.reset_after_load(physics_handler, timeline);
+    }
+
+    fn reset_after_load(&mut self, physics_handler: &mut PhysicsHandler, timeline: &mut Timeline) {
+        physics_handler.clear_maneuvers();
+        self.edit_history.clear();
+        self.group_edit = None;
+        self.discontinuity = true;
+        timeline.clear();
+        self.ghost_obj = None;
+        self.maneuver_node = None;
+        self.control_state = ControlState::Idle;
+    }
+
+    pub fn draw_menu(&self, x: f32, y: f32, font_size: f32, color: Color) {
+        if self.control_state == ControlState::Spawn {
+            self.draw_spawn_menu(x, y, font_size, color);
+        }
+
+        if !self.scenario_menu_open {
+            return;
+        }
+
+        draw_text("Scenarios [Tab to close]", x, y, font_size, color);
+        for (i, scenario) in Scenario::ALL.iter().enumerate() {
+            draw_text(
+                &format!("[{}] {}", i + 1, scenario.name()),
+                x,
+                y + (i + 1) as f32 * font_size,
+                font_size,
+                color,
+            );
+        }
+    }
+
+    fn draw_spawn_menu(&self, x: f32, y: f32, font_size: f32, color: Color) {
+        let tool = &self.spawn_tool;
+        let mass = if tool.spawns_particles() {
+            String::from("test particles")
+        } else {
+            format!("{:.2} - {:.2}", tool.mass_range.0, tool.mass_range.1)
+        };
+
+        let lines = [
+            String::from("Spawn tool [Enter to commit, Esc to cancel]"),
+            format!("[B] shape: {}", tool.shape.name()),
+            format!("[Up/Down] count: {}", tool.count),
+            format!(
+                "[[/]] [-/=] radius: {:.1} - {:.1}",
+                tool.radius_range.0, tool.radius_range.1
+            ),
+            format!("[X] mass: {mass}"),
+            format!("[M] velocity: {}", tool.velocity.name()),
+        ];
+
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(line, x, y + i as f32 * font_size, font_size, color);
+        }
+    }
+
+    pub fn set_export_dir(&mut self, dir: impl Into<String>) {
+        self.export_dir = dir.into();
+    }
+
+    fn handle_export(
+        &mut self,
+        objects: &ObjectPool,
+        physics_handler: &PhysicsHandler,
+        timeline: &Timeline,
+    ) {
+        if !is_key_released(KeyCode::F10) {
+            return;
+        }
+
+        let predicted = if self
+            .predictor
+            .is_current(objects, physics_handler, None, None)
+        {
+            self.predictor.trajectories().clone()
+        } else {
+            objects.calculate_trajectories(
+                physics_handler,
+                PREDICTION_POINTS,
+                PREDICTION_STEP,
+                None,
+                None,
+            )
+        };
+        let history = timeline.trajectories();
+        let per_object = is_key_down(KeyCode::LeftShift);
+
+        for (name, trajectories) in [("predicted", &predicted), ("history", &history)] {
+            for format in [ExportFormat::Csv, ExportFormat::Obj] {
+                let result = if per_object {
+                    export::export_per_object(&self.export_dir, name, format, trajectories)
+                        .map(|_| ())
+                } else {
+                    let path = format!("{}/{name}.{}", self.export_dir, format.extension());
+                    export::export_combined(path, format, trajectories)
+                };
+
+                if let Err(err) = result {
+                    eprintln!("Failed to export trajectories: {err}");
+                }
+            }
+        }
+    }
+
+    pub fn set_scene_path(&mut self, path: impl Into<String>) {
+        self.scene_path = path.into();
+    }
+
+    #[cfg(feature = "scene")]
+    fn handle_scene(
+        &mut self,
+        renderer: &mut Renderer,
+        objects: &mut ObjectPool,
+        physics_handler: &mut PhysicsHandler,
+        timeline: &mut Timeline,
+        units: &mut Units,
+    ) {
+        if is_key_released(KeyCode::F5) {
+            let scene = Scene::capture(objects, physics_handler, renderer, units);
+            if let Err(err) = scene.save(&self.scene_path) {
+                eprintln!("Failed to save scene: {err}");
+            }
+        }
+
+        if is_key_released(KeyCode::F9) {
+            match Scene::load(&self.scene_path) {
+                Ok(scene) => {
+                    scene.apply(objects, physics_handler, renderer, units);
+                    self.reset_after_load(physics_handler, timeline);
+                }
+                Err(err) => eprintln!("Failed to load scene: {err}"),
+            }
+        }
+    }
+
+    fn handle_group(&mut self, renderer: &Renderer, objects: &mut ObjectPool, dt: f32) {
+        let Some(group) = self.active_group.clone() else {
+            return;
+        };
+
+        for id in objects.group_ids(&group) {
+            if let Some(obj) = objects.get(id).map(|obj| self.to_display(obj)) {
+                renderer.draw_halo(
+                    obj.position,
+                    obj.radius * 1.2,
+                    Some(Color { a: 0.15, ..YELLOW }),
+                );
+            }
+        }
+
+        let dir = self.get_input_dir();
+        let transforming = dir != Vec3::ZERO
+            || [
+                KeyCode::E,
+                KeyCode::Q,
+                KeyCode::Up,
+                KeyCode::Down,
+                KeyCode::I,
+                KeyCode::J,
+                KeyCode::K,
+                KeyCode::L,
+            ]
+            .into_iter()
+            .any(is_key_down);
+        if !transforming {
+            self.finish_group_edit(objects);
+        } else if self.group_edit.is_none() {
+            self.group_edit = Some(
+                objects
+                    .group_ids(&group)
+                    .iter()
+                    .filter_map(|id| objects.get(*id).cloned())
+                    .collect(),
+            );
+        }
+
+        objects.translate_group(&group, dir.with_x(-dir.x) * self.move_speed * dt);
+
+        if is_key_down(KeyCode::E) {
+            objects.rotate_group(&group, Quat::from_rotation_y(dt));
+        }
+        if is_key_down(KeyCode::Q) {
+            objects.rotate_group(&group, Quat::from_rotation_y(-dt));
+        }
+        if is_key_down(KeyCode::Up) {
+            objects.scale_group(&group, 1. + dt);
+        }
+        if is_key_down(KeyCode::Down) {
+            objects.scale_group(&group, 1. - dt);
+        }
+
+        let mut boost = Vec3::ZERO;
+        if is_key_down(KeyCode::I) {
+            boost.z += 1.;
+        }
+        if is_key_down(KeyCode::K) {
+            boost.z -= 1.;
+        }
+        if is_key_down(KeyCode::J) {
+            boost.x += 1.;
+        }
+        if is_key_down(KeyCode::L) {
+            boost.x -= 1.;
+        }
+        objects.boost_group(&group, boost * self.scale_speed * dt * 0.1);
+
+        if is_key_released(KeyCode::C) {
+            self.finish_group_edit(objects);
+            self.group_count += 1;
+            let copy = format!("group {}", self.group_count);
+            let offset = vec3(0., 0., 20.);
+
+            let mut commands = Vec::new();
+            for id in objects.duplicate_group(&group, &copy, offset) {
+                if let Some(obj) = objects.get(id) {
+                    commands.push(EditCommand::Spawn(obj.clone()));
+                    commands.push(EditCommand::Join {
+                        group: copy.clone(),
+                        id,
+                    });
+                }
+            }
+            if !commands.is_empty() {
+                self.edit_history.record(EditCommand::Batch(commands));
+            }
+            self.active_group = Some(copy);
+        }
+
+        if is_key_released(KeyCode::R) {
+            self.finish_group_edit(objects);
+            self.edit_history
+                .delete_all(objects, &objects.group_ids(&group));
+            self.active_group = None;
+        }
+    }
+
+    fn finish_group_edit(&mut self, objects: &ObjectPool) {
+        let Some(before) = self.group_edit.take() else {
+            return;
+        };
+
+        let commands: Vec<EditCommand> = before
+            .into_iter()
+            .filter_map(|before| {
+                let after = objects.get(before.id)?.clone();
+                Some(EditCommand::Transform { before, after })
+            })
+            .collect();
+        if !commands.is_empty() {
+            self.edit_history.record(EditCommand::Batch(commands));
+        }
+    }
+
+    fn handle_frame(&mut self, objects: &mut ObjectPool, physics_handler: &mut PhysicsHandler) {
+        if is_key_released(KeyCode::O) {
+            objects.to_barycentric_frame();
+            objects.mark_edited();
+        }
+
+        if is_key_released(KeyCode::N) {
+            physics_handler.set_auto_recenter(!physics_handler.get_auto_recenter());
+        }
+
+        if is_key_released(KeyCode::F) {
+            self.camera_tracking = match self.camera_tracking {
+                CameraTracking::Off => CameraTracking::All,
+                CameraTracking::All => CameraTracking::Group,
+                CameraTracking::Group => CameraTracking::Off,
+            };
+        }
+    }
+
+    pub fn track_camera(&self, renderer: &mut Renderer, objects: &ObjectPool) {
+        let barycenter = match self.camera_tracking {
+            CameraTracking::Off => None,
+            CameraTracking::All => objects.center_of_mass(),
+            CameraTracking::Group => self
+                .active_group
+                .as_ref()
+                .and_then(|group| objects.group_center_of_mass(group)),
+        };
+
+        if let Some(barycenter) = barycenter {
+            let barycenter = self
+                .get_frame_transform(objects)
+                .map_or(barycenter, |transform| transform.point_to_frame(barycenter));
+            renderer.track_cam(barycenter);
+        }
+    }
+
+    fn handle_rotating_frame(
+        &mut self,
+        renderer: &mut Renderer,
+        objects: &mut ObjectPool,
+        physics_handler: &mut PhysicsHandler,
+    ) {
+        if is_key_released(KeyCode::T) {
+            match (self.get_hovered_obj(renderer, objects), self.frame_pick) {
+                (Some(obj), None) => self.frame_pick = Some(obj.id),
+                (Some(obj), Some(primary)) if obj.id != primary => {
+                    Self::leave_frame_rotation(objects, physics_handler);
+                    self.rotating_frame = Some(RotatingFrame::new(primary, obj.id));
+                    self.frame_pick = None;
+                }
+                _ => {
+                    self.rotating_frame = None;
+                    self.frame_pick = None;
+                }
+            }
+        }
+
+        if self.rotating_frame.is_some() && self.get_frame_transform(objects).is_none() {
+            self.rotating_frame = None;
+        }
+        if self.rotating_frame.is_none() {
+            Self::leave_frame_rotation(objects, physics_handler);
+        }
+
+        if let Some(obj) = self.frame_pick.and_then(|id| objects.get(id)) {
+            let obj = self.to_display(obj);
+            renderer.draw_halo(
+                obj.position,
+                obj.radius * 1.3,
+                Some(Color { a: 0.15, ..WHITE }),
+            );
+        }
+
+        if is_key_released(KeyCode::V) {
+            match physics_handler.get_frame_rotation() {
+                Some(_) => Self::leave_frame_rotation(objects, physics_handler),
+                None => {
+                    if let Some(transform) = self.frame_transform {
+                        *objects = transform.pool_to_frame(objects);
+                        objects.mark_edited();
+                        physics_handler
+                            .set_frame_rotation(Some(transform.frame_angular_velocity()));
+                    }
+                }
+            }
+        }
+    }
+
+    fn leave_frame_rotation(objects: &mut ObjectPool, physics_handler: &mut PhysicsHandler) {
+        let Some(omega) = physics_handler.get_frame_rotation() else {
+            return;
+        };
+
+        for obj in objects.iter_mut() {
+            obj.velocity += omega.cross(obj.position);
+        }
+        objects.mark_edited();
+        physics_handler.set_frame_rotation(None);
+    }
+
+    fn handle_trajectory_reference(&mut self, renderer: &mut Renderer, objects: &ObjectPool) {
+        if is_key_released(KeyCode::U) {
+            self.trajectory_reference = self
+                .get_hovered_obj(renderer, objects)
+                .map(|obj| obj.id)
+                .filter(|id| Some(*id) != self.trajectory_reference);
+        }
+
+        if self
+            .trajectory_reference
+            .is_some_and(|id| objects.get(id).is_none())
+        {
+            self.trajectory_reference = None;
+        }
+
+        if let Some(obj) = self.trajectory_reference.and_then(|id| objects.get(id)) {
+            let obj = self.to_display(obj);
+            renderer.draw_halo(
+                obj.position,
+                obj.radius * 1.3,
+                Some(Color { a: 0.15, ..SKYBLUE }),
+            );
+        }
+    }
+
+    pub fn get_trajectory_reference(&self) -> Option<usize> {
+        self.trajectory_reference
+    }
+
+    pub fn set_trajectory_reference(&mut self, reference: Option<usize>) {
+        self.trajectory_reference = reference;
+    }
+
+    fn handle_encounters(
+        &mut self,
+        renderer: &mut Renderer,
+        objects: &ObjectPool,
+        physics_handler: &PhysicsHandler,
+    ) {
+        if is_key_released(KeyCode::K) && !is_key_down(KeyCode::LeftAlt) {
+            match (self.get_hovered_obj(renderer, objects), self.encounter_pick) {
+                (Some(obj), None) => self.encounter_pick = Some(obj.id),
+                (Some(obj), Some(first)) if obj.id != first => {
+                    self.encounter_pair = Some((first, obj.id));
+                    self.encounter_pick = None;
+                }
+                _ => {
+                    self.encounter_pair = None;
+                    self.encounter_pick = None;
+                }
+            }
+        }
+
+        if let Some(obj) = self.encounter_pick.and_then(|id| objects.get(id)) {
+            let obj = self.to_display(obj);
+            renderer.draw_halo(
+                obj.position,
+                obj.radius * 1.3,
+                Some(Color { a: 0.15, ..ORANGE }),
+            );
+        }
+
+        self.encounters.clear();
+        let Some((first, second)) = self.encounter_pair else {
+            return;
+        };
+        let (Some(first), Some(second)) = (objects.get(first), objects.get(second)) else {
+            self.encounter_pair = None;
+            return;
+        };
+
+        self.predictor.update(
+            objects,
+            physics_handler,
+            self.rotating_frame.as_ref(),
+            self.trajectory_reference,
+        );
+        let (Some(first_traj), Some(second_traj)) =
+            (self.predictor.get(first.id), self.predictor.get(second.id))
+        else {
+            return;
+        };
+
+        let first_origin = self.trajectory_origin(objects, first_traj);
+        let second_origin = self.trajectory_origin(objects, second_traj);
+        first_traj.draw_from(renderer, first_origin, Some(first.color), first.radius);
+        second_traj.draw_from(renderer, second_origin, Some(second.color), second.radius);
+
+        self.encounters = find_encounters(first_traj, second_traj);
+        let closest = closest_approach(&self.encounters);
+
+        for encounter in &self.encounters {
+            let color = if Some(*encounter) == closest {
+                ORANGE
+            } else {
+                Color { a: 0.5, ..ORANGE }
+            };
+            let a = first_origin + encounter.first;
+            let b = second_origin + encounter.second;
+
+            renderer.draw_halo(a, first.radius * 0.5, Some(color));
+            renderer.draw_halo(b, second.radius * 0.5, Some(color));
+            renderer.draw_line(a, b, Some(color));
+        }
+    }
+
+    pub fn get_encounter_pair(&self) -> Option<(usize, usize)> {
+        self.encounter_pair
+    }
+
+    pub fn set_encounter_pair(&mut self, pair: Option<(usize, usize)>) {
+        self.encounter_pair = pair;
+    }
+
+    pub fn get_encounters(&self) -> &[Encounter] {
+        &self.encounters
+    }
+
+    pub fn draw_encounters(
+        &self,
+        units: &Units,
+        sim_time: f32,
+        x: f32,
+        y: f32,
+        font_size: f32,
+        color: Color,
+    ) {
+        let Some((first, second)) = self.encounter_pair else {
+            return;
+        };
+
+        draw_text(
+            &format!("Encounters: Object {first} / Object {second}"),
+            x,
+            y,
+            font_size,
+            color,
+        );
+
+        let closest = closest_approach(&self.encounters);
+        for (i, encounter) in self.encounters.iter().take(5).enumerate() {
+            let marker = if Some(*encounter) == closest {
+                "*"
+            } else {
+                " "
+            };
+            draw_text(
+                &format!(
+                    "{marker} in {}: {}",
+                    units.format(Quantity::Time, encounter.time - sim_time),
+                    units.format(Quantity::Length, encounter.distance)
+                ),
+                x,
+                y + (i + 1) as f32 * font_size,
+                font_size,
+                color,
+            );
+        }
+    }
+
+    fn update_orbit(
+        &mut self,
+        renderer: &Renderer,
+        physics_handler: &PhysicsHandler,
+        objects: &ObjectPool,
+        object: &Object,
+    ) {
+        let Some(primary) = dominant_body(objects, object) else {
+            return;
+        };
+        let Some(elements) = OrbitalElements::of(object, primary, physics_handler.get_grav_const())
+        else {
+            return;
+        };
+
+        let periapsis = self.to_display_point(primary.position + elements.periapsis);
+        let apoapsis = elements
+            .apoapsis
+            .map(|apoapsis| self.to_display_point(primary.position + apoapsis));
+
+        renderer.draw_halo(periapsis, object.radius * 0.5, Some(SKYBLUE));
+        if let Some(apoapsis) = apoapsis {
+            renderer.draw_halo(apoapsis, object.radius * 0.5, Some(PINK));
+        }
+
+        self.orbit = Some(OrbitReadout {
+            elements,
+            periapsis,
+            apoapsis,
+        });
+    }
+
+    pub fn get_orbit(&self) -> Option<OrbitalElements> {
+        self.orbit.map(|orbit| orbit.elements)
+    }
+
+    pub fn draw_orbit(
+        &self,
+        renderer: &Renderer,
+        units: &Units,
+        x: f32,
+        y: f32,
+        font_size: f32,
+        color: Color,
+    ) {
+        let Some(orbit) = self.orbit else {
+            return;
+        };
+        let elements = orbit.elements;
+
+        let lines = [
+            format!("Orbit around Object {}", elements.primary),
+            format!(
+                "a: {}",
+                units.format(Quantity::Length, elements.semi_major_axis)
+            ),
+            format!("e: {:.4}", elements.eccentricity),
+            format!(
+                "T: {}",
+                elements.period.map_or(String::from("unbound"), |period| {
+                    units.format(Quantity::Time, period)
+                })
+            ),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(line, x, y + i as f32 * font_size, font_size, color);
+        }
+
+        let markers = [
+            Some((
+                orbit.periapsis,
+                "Pe",
+                elements.periapsis_distance(),
+                SKYBLUE,
+            )),
+            orbit
+                .apoapsis
+                .zip(elements.apoapsis_distance())
+                .map(|(point, distance)| (point, "Ap", distance, PINK)),
+        ];
+        for (point, label, distance, marker_color) in markers.into_iter().flatten() {
+            if let Some(screen) = renderer.world_to_screen(point) {
+                draw_text(
+                    &format!("{label} {}", units.format(Quantity::Length, distance)),
+                    screen.x + 8.,
+                    screen.y,
+                    font_size,
+                    marker_color,
+                );
+            }
+        }
+    }
+
+    fn handle_lagrange(
+        &mut self,
+        renderer: &mut Renderer,
+        objects: &ObjectPool,
+        physics_handler: &PhysicsHandler,
+    ) {
+        if is_key_released(KeyCode::L) && !is_key_down(KeyCode::LeftAlt) {
+            match (self.get_hovered_obj(renderer, objects), self.lagrange_pick) {
+                (Some(obj), None) => self.lagrange_pick = Some(obj.id),
+                (Some(obj), Some(first)) if obj.id != first => {
+                    self.lagrange_pair = Some((first, obj.id));
+                    self.lagrange_pick = None;
+                }
+                _ => {
+                    self.lagrange_pair = None;
+                    self.lagrange_pick = None;
+                }
+            }
+        }
+
+        if let Some(obj) = self.lagrange_pick.and_then(|id| objects.get(id)) {
+            let obj = self.to_display(obj);
+            renderer.draw_halo(
+                obj.position,
+                obj.radius * 1.3,
+                Some(Color { a: 0.15, ..VIOLET }),
+            );
+        }
+
+        self.lagrange = self.lagrange_pair.and_then(|(first, second)| {
+            LagrangeSystem::new(objects, first, second, physics_handler.get_grav_const())
+        });
+        let Some(system) = self.lagrange else {
+            self.lagrange_pair = None;
+            return;
+        };
+
+        for point in system.points {
+            let point = self.to_display_point(point);
+            renderer.draw_point(point, 0.6, Some(VIOLET));
+        }
+
+        let spheres = [
+            (system.secondary, Some(system.hill_radius)),
+            (system.primary, system.primary_hill_radius),
+        ];
+        for (id, radius) in spheres {
+            if let (Some(obj), Some(radius)) = (objects.get(id), radius) {
+                let obj = self.to_display(obj);
+                renderer.draw_halo(obj.position, radius, Some(Color { a: 0.06, ..VIOLET }));
+            }
+        }
+    }
+
+    pub fn get_lagrange(&self) -> Option<LagrangeSystem> {
+        self.lagrange
+    }
+
+    pub fn set_lagrange_pair(&mut self, pair: Option<(usize, usize)>) {
+        self.lagrange_pair = pair;
+    }
+
+    pub fn draw_lagrange(
+        &self,
+        renderer: &Renderer,
+        units: &Units,
+        x: f32,
+        y: f32,
+        font_size: f32,
+        color: Color,
+    ) {
+        let Some(system) = self.lagrange else {
+            return;
+        };
+
+        for (i, point) in system.points.iter().enumerate() {
+            if let Some(screen) = renderer.world_to_screen(self.to_display_point(*point)) {
+                draw_text(
+                    &format!("L{}", i + 1),
+                    screen.x + 8.,
+                    screen.y,
+                    font_size,
+                    VIOLET,
+                );
+            }
+        }
+
+        let mut lines = vec![format!(
+            "Hill sphere of Object {}: {}",
+            system.secondary,
+            units.format(Quantity::Length, system.hill_radius)
+        )];
+        if let Some(radius) = system.primary_hill_radius {
+            lines.push(format!(
+                "Hill sphere of Object {}: {}",
+                system.primary,
+                units.format(Quantity::Length, radius)
+            ));
+        }
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(line, x, y + i as f32 * font_size, font_size, color);
+        }
+    }
+
+    fn handle_impacts(&mut self, objects: &ObjectPool, physics_handler: &PhysicsHandler) {
+        if is_key_released(KeyCode::F7) {
+            self.show_impacts = !self.show_impacts;
+        }
+
+        self.impacts.clear();
+        if !self.show_impacts || objects.is_empty() {
+            return;
+        }
+
+        self.predictor.update(
+            objects,
+            physics_handler,
+            self.rotating_frame.as_ref(),
+            self.trajectory_reference,
+        );
+
+        let sim_time = physics_handler.get_sim_time();
+        for (id, traj) in self.predictor.trajectories() {
+            let termination = traj.termination();
+            if objects.get(*id).is_none() || termination.time().is_none_or(|time| time < sim_time) {
+                continue;
+            }
+
+            if let Termination::Collision { other, .. } = termination
+                && let (Some(obj), Some(other)) = (objects.get(*id), objects.get(other))
+                && (obj.mass, obj.id) > (other.mass, other.id)
+            {
+                continue;
+            }
+
+            self.impacts.push((*id, termination));
+        }
+
+        self.impacts.sort_by(|a, b| {
+            a.1.time()
+                .unwrap_or(0.)
+                .total_cmp(&b.1.time().unwrap_or(0.))
+        });
+    }
+
+    pub fn is_showing_impacts(&self) -> bool {
+        self.show_impacts
+    }
+
+    pub fn get_impacts(&self) -> &[(usize, Termination)] {
+        &self.impacts
+    }
+
+    pub fn draw_impacts(
+        &self,
+        units: &Units,
+        sim_time: f32,
+        x: f32,
+        y: f32,
+        font_size: f32,
+        color: Color,
+    ) {
+        for (i, (id, termination)) in self.impacts.iter().take(8).enumerate() {
+            let text = match termination {
+                Termination::Collision { other, time } => format!(
+                    "Object {id} hits Object {other} in {}",
+                    units.format(Quantity::Time, time - sim_time)
+                ),
+                Termination::LeftDomain { time } => format!(
+                    "Object {id} leaves the domain in {}",
+                    units.format(Quantity::Time, time - sim_time)
+                ),
+                Termination::Horizon => continue,
+            };
+
+            draw_text(&text, x, y + i as f32 * font_size, font_size, color);
+        }
+    }
+
+    pub fn draw_hovered_influence(
+        &self,
+        renderer: &Renderer,
+        objects: &ObjectPool,
+        tracker: &InfluenceTracker,
+    ) {
+        if let Some(id) = self.hovered
+            && let Some(sphere) = tracker
+                .sphere_of(id)
+                .filter(|sphere| sphere.radius.is_finite())
+            && let Some(obj) = objects.get(id)
+        {
+            renderer.draw_halo(
+                self.to_display(obj).position,
+                sphere.radius,
+                Some(Color { a: 0.08, ..VIOLET }),
+            );
+        }
+    }
+
+    pub fn draw_influence(
+        &self,
+        tracker: &InfluenceTracker,
+        units: &Units,
+        x: f32,
+        y: f32,
+        font_size: f32,
+        color: Color,
+    ) {
+        let name =
+            |id: Option<usize>| id.map_or(String::from("no primary"), |id| format!("Object {id}"));
+        let mut lines = Vec::new();
+
+        if let Some(id) = self.hovered {
+            lines.push(match tracker.primary_of(id) {
+                Some(primary) => format!("Object {id} is in the SOI of Object {primary}"),
+                None => format!("Object {id} has no primary"),
+            });
+            if let Some(sphere) = tracker.sphere_of(id)
+                && sphere.radius.is_finite()
+            {
+                lines.push(format!(
+                    "SOI radius {}",
+                    units.format(Quantity::Length, sphere.radius)
+                ));
+            }
+        }
+
+        for event in tracker.history().rev() {
+            lines.push(format!(
+                "Object {} left {} for {} at {}",
+                event.object,
+                name(event.from),
+                name(event.to),
+                units.format(Quantity::Time, event.time)
+            ));
+        }
+
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(line, x, y + i as f32 * font_size, font_size, color);
+        }
+    }
+
+    fn trajectory_origin(&self, objects: &ObjectPool, traj: &Trajectory) -> Vec3 {
+        traj.reference()
+            .and_then(|id| objects.get(id))
+            .map_or(Vec3::ZERO, |obj| self.to_display(obj).position)
+    }
+
+    pub fn get_rotating_frame(&self) -> Option<RotatingFrame> {
+        self.rotating_frame
+    }
+
+    pub fn set_rotating_frame(&mut self, rotating_frame: Option<RotatingFrame>) {
+        self.rotating_frame = rotating_frame;
+    }
+
+    pub fn get_frame_transform(&self, objects: &ObjectPool) -> Option<FrameTransform> {
+        self.rotating_frame
+            .and_then(|frame| frame.transform(objects))
+    }
+
+    fn to_display_point(&self, point: Vec3) -> Vec3 {
+        self.frame_transform
+            .map_or(point, |transform| transform.point_to_frame(point))
+    }
+
+    fn to_display(&self, obj: &Object) -> Object {
+        self.frame_transform
+            .map_or(obj.clone(), |transform| transform.object_to_frame(obj))
+    }
+
+    pub fn get_camera_tracking(&self) -> CameraTracking {
+        self.camera_tracking
+    }
+
+    pub fn set_camera_tracking(&mut self, camera_tracking: CameraTracking) {
+        self.camera_tracking = camera_tracking;
+    }
+
+    pub fn get_active_group(&self) -> Option<&str> {
+        self.active_group.as_deref()
+    }
+
+    fn is_chord_active() -> bool {
+        (is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl))
+            && (is_key_down(KeyCode::Z) || is_key_down(KeyCode::Y))
+    }
+
+    fn is_scrubbing(timeline: &Timeline) -> bool {
+        timeline.is_paused() && (is_key_down(KeyCode::Left) || is_key_down(KeyCode::Right))
+    }
+
     fn handle_movement(&mut self, renderer: &mut Renderer, dt: f32) {
         renderer.move_cam(self.get_input_dir() * self.move_speed * dt);
     }
 
     fn handle_ghost_obj(&mut self, dt: f32) {
         if let Some(obj) = &mut self.ghost_obj {
-            if is_key_down(KeyCode::Up) {
+            if is_key_released(KeyCode::X) {
+                *obj = if obj.test_particle {
+                    Object::new(obj.position, obj.velocity, 1.0, 1.0, obj.color)
+                } else {
+                    Object::particle(obj.position, obj.velocity, obj.color)
+                };
+            }
+
+            if is_key_down(KeyCode::Up) && !obj.test_particle {
                 obj.mass += self.scale_speed * dt;
                 obj.radius += self.scale_speed * dt;
             }
@@ -201,11 +1719,12 @@ impl ControlHandler {
     fn get_hovered_obj(&mut self, renderer: &mut Renderer, objects: &ObjectPool) -> Option<Object> {
         let ray = Ray::new_from_mouse(renderer.get_cam());
         for obj in objects.iter() {
+            let obj = self.to_display(obj);
             if !ray.raycast(obj.position, obj.radius) {
                 continue;
             }
 
-            return Some(obj.clone());
+            return Some(obj);
         }
 
         None
@@ -218,8 +1737,25 @@ impl ControlHandler {
         objects: &ObjectPool,
         object: &Object,
     ) {
-        let traj = object.calculate_trajectory(objects, physics_handler, 10_000, 2);
-        traj.draw(renderer, Some(object.color), object.radius);
+        self.predictor.update(
+            objects,
+            physics_handler,
+            self.rotating_frame.as_ref(),
+            self.trajectory_reference,
+        );
+
+        if let Some(traj) = self.predictor.get(object.id) {
+            let origin = self.trajectory_origin(objects, traj);
+            traj.draw_from(renderer, origin, Some(object.color), object.radius);
+
+            for node in physics_handler.get_maneuvers() {
+                if node.object == object.id
+                    && let Some(point) = traj.sample_at(node.time)
+                {
+                    renderer.draw_halo(origin + point, object.radius * 0.8, Some(SKYBLUE));
+                }
+            }
+        }
     }
 
     fn draw_objects_trajectories(
@@ -228,24 +1764,21 @@ impl ControlHandler {
         renderer: &Renderer,
         objects: &ObjectPool,
     ) {
-        self.trajectories = objects.calculate_trajectories(physics_handler, 10_000, 2);
+        self.drag_predictor.update(
+            objects,
+            physics_handler,
+            self.rotating_frame.as_ref(),
+            self.trajectory_reference,
+        );
 
-        for (id, traj) in &self.trajectories {
+        for (id, traj) in self.drag_predictor.trajectories() {
             let obj = match objects.get(*id) {
                 Some(obj) => obj,
                 None => continue,
             };
 
-            traj.draw(renderer, Some(obj.color), obj.radius);
-        }
-    }
-
-    fn random_color() -> Color {
-        Color {
-            r: rand::gen_range(0.0, 1.0),
-            g: rand::gen_range(0.0, 1.0),
-            b: rand::gen_range(0.0, 1.0),
-            a: 1.0,
+            let origin = self.trajectory_origin(objects, traj);
+            traj.draw_from(renderer, origin, Some(obj.color), obj.radius);
         }
     }
 }
@@ -256,9 +1789,26 @@ impl Default for ControlHandler {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct OrbitReadout {
+    elements: OrbitalElements,
+    periapsis: Vec3,
+    apoapsis: Option<Vec3>,
+}
+
 #[derive(Debug, PartialEq)]
 enum ControlState {
     Idle,
     Place,
     Drag,
+    Spawn,
+    Maneuver,
+    Move,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CameraTracking {
+    Off,
+    All,
+    Group,
 }
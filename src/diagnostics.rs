@@ -0,0 +1,75 @@
+use crate::object::ObjectPool;
+use crate::physics::PhysicsHandler;
+use crate::units::{Quantity, Units};
+use macroquad::prelude::*;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Diagnostics {
+    pub momentum: Vec3,
+    pub orbital_angular_momentum: Vec3,
+    pub spin_angular_momentum: Vec3,
+    pub kinetic_energy: f32,
+    pub potential_energy: f32,
+}
+
+impl Diagnostics {
+    pub fn measure(objects: &ObjectPool, physics_handler: &PhysicsHandler) -> Self {
+        let mut diagnostics = Diagnostics::default();
+
+        for obj in objects.iter() {
+            diagnostics.momentum += obj.mass * obj.velocity;
+            diagnostics.orbital_angular_momentum += obj.orbital_angular_momentum();
+            diagnostics.spin_angular_momentum += obj.spin_angular_momentum();
+            diagnostics.kinetic_energy += 0.5 * obj.mass * obj.velocity.length_squared()
+                + 0.5 * obj.moment_of_inertia() * obj.spin.length_squared();
+        }
+
+        let objs: Vec<_> = objects.iter().collect();
+        for (i, a) in objs.iter().enumerate() {
+            for b in &objs[i + 1..] {
+                let dist = (a.position - b.position).length();
+                if dist == 0. {
+                    continue;
+                }
+
+                diagnostics.potential_energy -=
+                    physics_handler.get_grav_const() * a.mass * b.mass / dist;
+            }
+        }
+
+        diagnostics
+    }
+
+    pub fn total_energy(&self) -> f32 {
+        self.kinetic_energy + self.potential_energy
+    }
+
+    pub fn total_angular_momentum(&self) -> Vec3 {
+        self.orbital_angular_momentum + self.spin_angular_momentum
+    }
+
+    pub fn draw(&self, units: &Units, x: f32, y: f32, font_size: f32, color: Color) {
+        let lines = [
+            format!("E: {}", units.format(Quantity::Energy, self.total_energy())),
+            format!(
+                "|p|: {}",
+                units.format(Quantity::Momentum, self.momentum.length())
+            ),
+            format!(
+                "|L|: {} (spin {})",
+                units.format(
+                    Quantity::AngularMomentum,
+                    self.total_angular_momentum().length()
+                ),
+                units.format(
+                    Quantity::AngularMomentum,
+                    self.spin_angular_momentum.length()
+                )
+            ),
+        ];
+
+        for (i, line) in lines.iter().enumerate() {
+            draw_text(line, x, y + i as f32 * font_size, font_size, color);
+        }
+    }
+}
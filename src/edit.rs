@@ -0,0 +1,199 @@
+use crate::object::{Object, ObjectPool};
+use macroquad::prelude::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EditCommand {
+    Spawn(Object),
+    Delete(Object),
+    Edit { before: Object, after: Object },
+    Move { id: usize, from: Vec3, to: Vec3 },
+    Transform { before: Object, after: Object },
+    Join { group: String, id: usize },
+    Leave { group: String, id: usize },
+    Batch(Vec<EditCommand>),
+}
+
+impl EditCommand {
+    pub fn apply(&self, objects: &mut ObjectPool) {
+        objects.mark_edited();
+        match self {
+            EditCommand::Spawn(obj) => objects.insert(obj.clone()),
+            EditCommand::Delete(obj) => objects.remove(obj.id),
+            EditCommand::Edit { after, .. } => Self::set_properties(objects, after),
+            EditCommand::Move { id, to, .. } => Self::set_position(objects, *id, *to),
+            EditCommand::Transform { after, .. } => Self::set_state(objects, after),
+            EditCommand::Join { group, id } => objects.add_to_group(group, *id),
+            EditCommand::Leave { group, id } => objects.remove_from_group(group, *id),
+            EditCommand::Batch(commands) => {
+                for command in commands {
+                    command.apply(objects);
+                }
+            }
+        }
+    }
+
+    pub fn revert(&self, objects: &mut ObjectPool) {
+        objects.mark_edited();
+        match self {
+            EditCommand::Spawn(obj) => objects.remove(obj.id),
+            EditCommand::Delete(obj) => objects.insert(obj.clone()),
+            EditCommand::Edit { before, .. } => Self::set_properties(objects, before),
+            EditCommand::Move { id, from, .. } => Self::set_position(objects, *id, *from),
+            EditCommand::Transform { before, .. } => Self::set_state(objects, before),
+            EditCommand::Join { group, id } => objects.remove_from_group(group, *id),
+            EditCommand::Leave { group, id } => objects.add_to_group(group, *id),
+            EditCommand::Batch(commands) => {
+                for command in commands.iter().rev() {
+                    command.revert(objects);
+                }
+            }
+        }
+    }
+
+    fn set_properties(objects: &mut ObjectPool, source: &Object) {
+        if let Some(obj) = objects.get_mut(source.id) {
+            obj.mass = source.mass;
+            obj.radius = source.radius;
+            obj.color = source.color;
+            obj.spin = source.spin;
+        }
+    }
+
+    fn set_state(objects: &mut ObjectPool, source: &Object) {
+        if let Some(obj) = objects.get_mut(source.id) {
+            obj.position = source.position;
+            obj.velocity = source.velocity;
+            obj.orientation = source.orientation;
+        }
+    }
+
+    fn set_position(objects: &mut ObjectPool, id: usize, position: Vec3) {
+        if let Some(obj) = objects.get_mut(id) {
+            obj.position = position;
+        }
+    }
+}
+
+pub struct EditHistory {
+    undo_stack: Vec<EditCommand>,
+    redo_stack: Vec<EditCommand>,
+    capacity: usize,
+}
+
+impl EditHistory {
+    pub fn new(capacity: usize) -> Self {
+        EditHistory {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            capacity,
+        }
+    }
+
+    pub fn spawn(&mut self, objects: &mut ObjectPool, object: Object) -> usize {
+        let id = objects.push(object);
+        if let Some(obj) = objects.get(id) {
+            self.record(EditCommand::Spawn(obj.clone()));
+        }
+
+        id
+    }
+
+    pub fn spawn_all(&mut self, objects: &mut ObjectPool, batch: Vec<Object>) -> Vec<usize> {
+        let mut commands = Vec::with_capacity(batch.len());
+        let ids = batch
+            .into_iter()
+            .map(|object| {
+                let id = objects.push(object);
+                if let Some(obj) = objects.get(id) {
+                    commands.push(EditCommand::Spawn(obj.clone()));
+                }
+                id
+            })
+            .collect();
+
+        if !commands.is_empty() {
+            self.record(EditCommand::Batch(commands));
+        }
+
+        ids
+    }
+
+    pub fn delete(&mut self, objects: &mut ObjectPool, id: usize) {
+        self.delete_all(objects, &[id]);
+    }
+
+    pub fn delete_all(&mut self, objects: &mut ObjectPool, ids: &[usize]) {
+        let mut commands = Vec::new();
+        for id in ids {
+            let Some(obj) = objects.get(*id) else {
+                continue;
+            };
+
+            for group in objects.groups_of(*id) {
+                commands.push(EditCommand::Leave { group, id: *id });
+            }
+            commands.push(EditCommand::Delete(obj.clone()));
+        }
+
+        match commands.len() {
+            0 => {}
+            1 => self.execute(objects, commands.remove(0)),
+            _ => self.execute(objects, EditCommand::Batch(commands)),
+        }
+    }
+
+    pub fn execute(&mut self, objects: &mut ObjectPool, command: EditCommand) {
+        command.apply(objects);
+        self.record(command);
+    }
+
+    pub fn record(&mut self, command: EditCommand) {
+        self.redo_stack.clear();
+        self.undo_stack.push(command);
+
+        if self.undo_stack.len() > self.capacity {
+            self.undo_stack.remove(0);
+        }
+    }
+
+    pub fn undo(&mut self, objects: &mut ObjectPool) -> bool {
+        match self.undo_stack.pop() {
+            Some(command) => {
+                command.revert(objects);
+                self.redo_stack.push(command);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self, objects: &mut ObjectPool) -> bool {
+        match self.redo_stack.pop() {
+            Some(command) => {
+                command.apply(objects);
+                self.undo_stack.push(command);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        EditHistory::new(100)
+    }
+}
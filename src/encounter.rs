@@ -0,0 +1,53 @@
+use crate::object::Trajectory;
+use macroquad::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Encounter {
+    pub time: f32,
+    pub distance: f32,
+    pub first: Vec3,
+    pub second: Vec3,
+}
+
+pub fn find_encounters(first: &Trajectory, second: &Trajectory) -> Vec<Encounter> {
+    let mut times: Vec<f32> = first.times().chain(second.times()).copied().collect();
+    times.sort_by(f32::total_cmp);
+    times.dedup();
+
+    let samples: Vec<Encounter> = times
+        .into_iter()
+        .filter_map(|time| {
+            let (a, b) = (first.sample_at(time)?, second.sample_at(time)?);
+            Some(Encounter {
+                time,
+                distance: a.distance(b),
+                first: a,
+                second: b,
+            })
+        })
+        .collect();
+
+    let mut encounters: Vec<Encounter> = samples
+        .windows(3)
+        .filter(|window| {
+            window[1].distance < window[0].distance && window[1].distance <= window[2].distance
+        })
+        .map(|window| window[1])
+        .collect();
+
+    if let Some(closest) = closest_approach(&samples)
+        && !encounters.contains(&closest)
+    {
+        encounters.push(closest);
+        encounters.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+
+    encounters
+}
+
+pub fn closest_approach(encounters: &[Encounter]) -> Option<Encounter> {
+    encounters
+        .iter()
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+        .copied()
+}
@@ -0,0 +1,109 @@
+use crate::object::Trajectory;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+pub const TRAJECTORY_CSV_HEADER: &str = "id,time,x,y,z";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Obj,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Obj => "obj",
+        }
+    }
+
+    pub fn write(
+        &self,
+        writer: &mut impl Write,
+        trajectories: &HashMap<usize, Trajectory>,
+    ) -> io::Result<()> {
+        match self {
+            ExportFormat::Csv => write_csv(writer, trajectories),
+            ExportFormat::Obj => write_obj(writer, trajectories),
+        }
+    }
+}
+
+pub fn write_csv(
+    writer: &mut impl Write,
+    trajectories: &HashMap<usize, Trajectory>,
+) -> io::Result<()> {
+    writeln!(writer, "{TRAJECTORY_CSV_HEADER}")?;
+
+    for (id, traj) in sorted(trajectories) {
+        for (time, point) in traj.times().zip(traj.iter()) {
+            writeln!(writer, "{id},{time},{},{},{}", point.x, point.y, point.z)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn write_obj(
+    writer: &mut impl Write,
+    trajectories: &HashMap<usize, Trajectory>,
+) -> io::Result<()> {
+    let mut offset = 1;
+
+    for (id, traj) in sorted(trajectories) {
+        writeln!(writer, "o object_{id}")?;
+        for point in traj.iter() {
+            writeln!(writer, "v {} {} {}", point.x, point.y, point.z)?;
+        }
+
+        if traj.len() > 1 {
+            let indices: Vec<String> = (offset..offset + traj.len())
+                .map(|index| index.to_string())
+                .collect();
+            writeln!(writer, "l {}", indices.join(" "))?;
+        }
+        offset += traj.len();
+    }
+
+    Ok(())
+}
+
+pub fn export_combined(
+    path: impl AsRef<Path>,
+    format: ExportFormat,
+    trajectories: &HashMap<usize, Trajectory>,
+) -> io::Result<()> {
+    if let Some(parent) = path.as_ref().parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    format.write(&mut writer, trajectories)?;
+    writer.flush()
+}
+
+pub fn export_per_object(
+    dir: impl AsRef<Path>,
+    prefix: &str,
+    format: ExportFormat,
+    trajectories: &HashMap<usize, Trajectory>,
+) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+
+    for (id, traj) in sorted(trajectories) {
+        let path = dir
+            .as_ref()
+            .join(format!("{prefix}_{id}.{}", format.extension()));
+        export_combined(&path, format, &HashMap::from([(id, traj.clone())]))?;
+        paths.push(path);
+    }
+
+    Ok(paths)
+}
+
+fn sorted(trajectories: &HashMap<usize, Trajectory>) -> BTreeMap<usize, &Trajectory> {
+    trajectories.iter().map(|(id, traj)| (*id, traj)).collect()
+}
@@ -0,0 +1,114 @@
+use crate::object::{Object, ObjectPool};
+use macroquad::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RotatingFrame {
+    pub primary: usize,
+    pub secondary: usize,
+}
+
+impl RotatingFrame {
+    pub fn new(primary: usize, secondary: usize) -> Self {
+        RotatingFrame { primary, secondary }
+    }
+
+    pub fn transform(&self, objects: &ObjectPool) -> Option<FrameTransform> {
+        let primary = objects.get(self.primary)?;
+        let secondary = objects.get(self.secondary)?;
+
+        let total_mass = primary.mass + secondary.mass;
+        if total_mass == 0. {
+            return None;
+        }
+
+        let origin =
+            (primary.mass * primary.position + secondary.mass * secondary.position) / total_mass;
+        let origin_velocity =
+            (primary.mass * primary.velocity + secondary.mass * secondary.velocity) / total_mass;
+
+        let separation = secondary.position - primary.position;
+        if separation.length_squared() == 0. {
+            return None;
+        }
+
+        let angular_velocity =
+            separation.cross(secondary.velocity - primary.velocity) / separation.length_squared();
+
+        let x_axis = separation.normalize();
+        let up = if angular_velocity.length_squared() > 0. {
+            angular_velocity.normalize()
+        } else {
+            Vec3::Y
+        };
+        let z_axis = x_axis.cross(up).normalize_or_zero();
+        if z_axis == Vec3::ZERO {
+            return None;
+        }
+        let y_axis = z_axis.cross(x_axis);
+
+        Some(FrameTransform {
+            origin,
+            origin_velocity,
+            rotation: Quat::from_mat3(&Mat3::from_cols(x_axis, y_axis, z_axis)),
+            angular_velocity,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameTransform {
+    pub origin: Vec3,
+    pub origin_velocity: Vec3,
+    pub rotation: Quat,
+    pub angular_velocity: Vec3,
+}
+
+impl FrameTransform {
+    pub fn point_to_frame(&self, point: Vec3) -> Vec3 {
+        self.rotation.inverse() * (point - self.origin)
+    }
+
+    pub fn point_to_world(&self, point: Vec3) -> Vec3 {
+        self.origin + self.rotation * point
+    }
+
+    pub fn velocity_to_frame(&self, point: Vec3, velocity: Vec3) -> Vec3 {
+        let relative = point - self.origin;
+        self.rotation.inverse()
+            * (velocity - self.origin_velocity - self.angular_velocity.cross(relative))
+    }
+
+    pub fn velocity_to_world(&self, point: Vec3, velocity: Vec3) -> Vec3 {
+        let relative = self.rotation * point;
+        self.origin_velocity + self.rotation * velocity + self.angular_velocity.cross(relative)
+    }
+
+    pub fn frame_angular_velocity(&self) -> Vec3 {
+        self.rotation.inverse() * self.angular_velocity
+    }
+
+    pub fn object_to_frame(&self, object: &Object) -> Object {
+        let mut obj = object.clone();
+        obj.position = self.point_to_frame(object.position);
+        obj.velocity = self.velocity_to_frame(object.position, object.velocity);
+        obj.orientation = self.rotation.inverse() * object.orientation;
+        obj
+    }
+
+    pub fn object_to_world(&self, object: &Object) -> Object {
+        let mut obj = object.clone();
+        obj.position = self.point_to_world(object.position);
+        obj.velocity = self.velocity_to_world(object.position, object.velocity);
+        obj.orientation = self.rotation * object.orientation;
+        obj
+    }
+
+    pub fn pool_to_frame(&self, objects: &ObjectPool) -> ObjectPool {
+        let mut pool = objects.clone();
+        for obj in pool.iter_mut() {
+            *obj = self.object_to_frame(obj);
+        }
+
+        pool
+    }
+}
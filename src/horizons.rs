@@ -0,0 +1,349 @@
+use crate::object::{Object, ObjectPool};
+use crate::units::{AU_M, DAY_S, Quantity, SOLAR_MASS_KG, Units};
+use macroquad::math::DVec3;
+use macroquad::prelude::*;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+pub const AU_KM: f64 = AU_M / 1_000.;
+pub const SUN_GM_KM3_S2: f64 = 1.327_124_400_18e11;
+
+#[derive(Debug)]
+pub enum HorizonsError {
+    Io(std::io::Error),
+    MissingTable,
+    Parse(String),
+}
+
+impl fmt::Display for HorizonsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HorizonsError::Io(err) => write!(f, "horizons io error: {err}"),
+            HorizonsError::MissingTable => write!(f, "no $$SOE/$$EOE vector table found"),
+            HorizonsError::Parse(line) => write!(f, "could not parse line: {line}"),
+        }
+    }
+}
+
+impl std::error::Error for HorizonsError {}
+
+impl From<std::io::Error> for HorizonsError {
+    fn from(err: std::io::Error) -> Self {
+        HorizonsError::Io(err)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HorizonsUnits {
+    AuDay,
+    KmSec,
+    KmDay,
+}
+
+impl HorizonsUnits {
+    fn length_to_au(&self) -> f64 {
+        match self {
+            HorizonsUnits::AuDay => 1.,
+            HorizonsUnits::KmSec | HorizonsUnits::KmDay => 1. / AU_KM,
+        }
+    }
+
+    fn time_to_day(&self) -> f64 {
+        match self {
+            HorizonsUnits::AuDay | HorizonsUnits::KmDay => 1.,
+            HorizonsUnits::KmSec => 1. / DAY_S,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HorizonsRecord {
+    pub julian_date: f64,
+    pub position: DVec3,
+    pub velocity: DVec3,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HorizonsTable {
+    pub name: String,
+    pub units: HorizonsUnits,
+    pub gm: Option<f64>,
+    pub radius_km: Option<f64>,
+    pub records: Vec<HorizonsRecord>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HorizonsScale {
+    pub units: Units,
+    pub radius_scale: f64,
+    pub min_radius: f32,
+    pub max_radius: f32,
+}
+
+impl HorizonsScale {
+    pub fn mass_per_solar_mass(&self) -> f64 {
+        self.units.from_si(Quantity::Mass, SOLAR_MASS_KG) as f64
+    }
+
+    pub fn position(&self, au: DVec3) -> Vec3 {
+        Self::to_ground_plane(au * AU_M / self.units.length)
+    }
+
+    pub fn velocity(&self, au_per_day: DVec3) -> Vec3 {
+        Self::to_ground_plane(au_per_day * AU_M / DAY_S * self.units.time / self.units.length)
+    }
+
+    fn to_ground_plane(vector: DVec3) -> Vec3 {
+        DVec3::new(vector.x, vector.z, -vector.y).as_vec3()
+    }
+}
+
+impl Default for HorizonsScale {
+    fn default() -> Self {
+        HorizonsScale {
+            units: Units::astronomical(50., 1., 1.),
+            radius_scale: 1_000.,
+            min_radius: 0.3,
+            max_radius: 4.,
+        }
+    }
+}
+
+impl HorizonsTable {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, HorizonsError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, HorizonsError> {
+        let mut name = String::from("Unknown");
+        let mut units = HorizonsUnits::KmSec;
+        let mut gm = None;
+        let mut radius_km = None;
+        let mut records = Vec::new();
+        let mut in_table = false;
+        let mut found_table = false;
+
+        for line in text.lines() {
+            let trimmed = line.trim();
+
+            if trimmed.starts_with("$$SOE") {
+                in_table = true;
+                found_table = true;
+                continue;
+            }
+            if trimmed.starts_with("$$EOE") {
+                in_table = false;
+                continue;
+            }
+
+            if in_table {
+                Self::parse_table_line(trimmed, &mut records)?;
+                continue;
+            }
+
+            if let Some(rest) = trimmed.strip_prefix("Target body name:") {
+                name = rest
+                    .split(['(', '{'])
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string();
+            } else if trimmed.starts_with("Output units") {
+                units = if trimmed.contains("AU-D") {
+                    HorizonsUnits::AuDay
+                } else if trimmed.contains("KM-D") {
+                    HorizonsUnits::KmDay
+                } else {
+                    HorizonsUnits::KmSec
+                };
+            }
+
+            if gm.is_none() && trimmed.contains("GM") && trimmed.contains("km^3/s^2") {
+                gm = Self::value_after(trimmed, "GM");
+            }
+            if radius_km.is_none() {
+                radius_km = Self::radius_km(trimmed);
+            }
+        }
+
+        if !found_table || records.is_empty() {
+            return Err(HorizonsError::MissingTable);
+        }
+
+        Ok(HorizonsTable {
+            name,
+            units,
+            gm,
+            radius_km,
+            records,
+        })
+    }
+
+    pub fn mass_solar(&self) -> Option<f64> {
+        self.gm.map(|gm| gm / SUN_GM_KM3_S2)
+    }
+
+    pub fn position_au(&self, record: &HorizonsRecord) -> DVec3 {
+        record.position * self.units.length_to_au()
+    }
+
+    pub fn velocity_au_per_day(&self, record: &HorizonsRecord) -> DVec3 {
+        record.velocity * self.units.length_to_au() / self.units.time_to_day()
+    }
+
+    pub fn to_object(
+        &self,
+        record: usize,
+        scale: &HorizonsScale,
+        fallback_mass_solar: f64,
+    ) -> Option<Object> {
+        let record = self.records.get(record)?;
+        let mass = self.mass_solar().unwrap_or(fallback_mass_solar) * scale.mass_per_solar_mass();
+        let radius = self.radius_km.map_or(scale.min_radius, |radius| {
+            ((radius * 1_000. / scale.units.length * scale.radius_scale) as f32)
+                .clamp(scale.min_radius, scale.max_radius)
+        });
+
+        let color = Color::from_hex(Self::name_hash(&self.name) | 0x40_40_40);
+
+        Some(
+            Object::new(
+                scale.position(self.position_au(record)),
+                scale.velocity(self.velocity_au_per_day(record)),
+                mass as f32,
+                radius,
+                color,
+            )
+            .with_name(self.name.clone()),
+        )
+    }
+
+    fn parse_table_line(
+        line: &str,
+        records: &mut Vec<HorizonsRecord>,
+    ) -> Result<(), HorizonsError> {
+        if line.is_empty() {
+            return Ok(());
+        }
+
+        if line.contains(',') && !line.contains('=') {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let values: Option<Vec<f64>> = fields
+                .iter()
+                .skip(2)
+                .take(6)
+                .map(|field| field.parse().ok())
+                .collect();
+
+            return match (fields[0].parse(), values) {
+                (Ok(julian_date), Some(values)) if values.len() == 6 => {
+                    records.push(HorizonsRecord {
+                        julian_date,
+                        position: DVec3::new(values[0], values[1], values[2]),
+                        velocity: DVec3::new(values[3], values[4], values[5]),
+                    });
+                    Ok(())
+                }
+                _ => Err(HorizonsError::Parse(line.to_string())),
+            };
+        }
+
+        let spaced = line.replace('=', " = ");
+        let tokens: Vec<&str> = spaced.split_whitespace().collect();
+
+        if tokens.len() > 1
+            && tokens[1] == "="
+            && let Ok(julian_date) = tokens[0].parse()
+        {
+            records.push(HorizonsRecord {
+                julian_date,
+                position: DVec3::ZERO,
+                velocity: DVec3::ZERO,
+            });
+            return Ok(());
+        }
+
+        let Some(record) = records.last_mut() else {
+            return Err(HorizonsError::Parse(line.to_string()));
+        };
+
+        for window in tokens.windows(3) {
+            if window[1] != "=" {
+                continue;
+            }
+
+            let value = || {
+                window[2]
+                    .parse::<f64>()
+                    .map_err(|_| HorizonsError::Parse(line.to_string()))
+            };
+
+            match window[0] {
+                "X" => record.position.x = value()?,
+                "Y" => record.position.y = value()?,
+                "Z" => record.position.z = value()?,
+                "VX" => record.velocity.x = value()?,
+                "VY" => record.velocity.y = value()?,
+                "VZ" => record.velocity.z = value()?,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn radius_km(line: &str) -> Option<f64> {
+        let rest = &line[line.find("adius")?..];
+        let (key, value) = rest.split_once('=')?;
+        let in_km = key.contains("km") || value.split_whitespace().nth(1) == Some("km");
+
+        if in_km {
+            Self::value_after(rest, "adius")
+        } else {
+            None
+        }
+    }
+
+    fn value_after(line: &str, key: &str) -> Option<f64> {
+        let rest = &line[line.find(key)?..];
+        let rest = rest[rest.find('=')? + 1..].trim_start();
+
+        (1..=rest.len())
+            .rev()
+            .filter(|end| rest.is_char_boundary(*end))
+            .find_map(|end| rest[..end].parse().ok())
+    }
+
+    fn name_hash(name: &str) -> u32 {
+        name.bytes().fold(0x811c_9dc5u32, |hash, byte| {
+            (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+        }) & 0xff_ff_ff
+    }
+}
+
+pub fn import_system(
+    tables: &[HorizonsTable],
+    scale: &HorizonsScale,
+    fallback_mass_solar: f64,
+) -> ObjectPool {
+    let mut objects = ObjectPool::new();
+
+    for table in tables {
+        if let Some(obj) = table.to_object(0, scale, fallback_mass_solar) {
+            objects.push(obj);
+        }
+    }
+
+    objects
+}
+
+pub fn load_dir(dir: impl AsRef<Path>) -> Result<Vec<HorizonsTable>, HorizonsError> {
+    let mut paths: Vec<_> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    paths.sort();
+
+    paths.into_iter().map(HorizonsTable::load).collect()
+}
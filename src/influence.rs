@@ -0,0 +1,133 @@
+use crate::object::{Object, ObjectPool};
+use crate::orbit::laplace_radius;
+use macroquad::prelude::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+const EVENT_HISTORY: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SphereOfInfluence {
+    pub body: usize,
+    pub primary: Option<usize>,
+    pub radius: f32,
+    pub position: Vec3,
+    pub mass: f32,
+}
+
+pub fn spheres_of_influence(objects: &ObjectPool, grav_const: f32) -> Vec<SphereOfInfluence> {
+    let mut bodies: Vec<&Object> = objects
+        .iter()
+        .filter(|obj| !obj.test_particle && obj.mass > 0.)
+        .collect();
+    bodies.sort_by(|a, b| b.mass.total_cmp(&a.mass));
+
+    let mut spheres: Vec<SphereOfInfluence> = Vec::with_capacity(bodies.len());
+    for body in bodies.iter().copied() {
+        let primary = innermost(&spheres, body).map(|index| bodies[index]);
+
+        spheres.push(SphereOfInfluence {
+            body: body.id,
+            primary: primary.map(|primary| primary.id),
+            radius: primary.map_or(f32::INFINITY, |primary| {
+                laplace_radius(body, primary, grav_const)
+            }),
+            position: body.position,
+            mass: body.mass,
+        });
+    }
+
+    spheres
+}
+
+pub fn assign_primaries(
+    objects: &ObjectPool,
+    spheres: &[SphereOfInfluence],
+) -> HashMap<usize, usize> {
+    objects
+        .iter()
+        .filter_map(|obj| innermost(spheres, obj).map(|index| (obj.id, spheres[index].body)))
+        .collect()
+}
+
+fn innermost(spheres: &[SphereOfInfluence], object: &Object) -> Option<usize> {
+    spheres
+        .iter()
+        .enumerate()
+        .filter(|(_, sphere)| {
+            sphere.body != object.id
+                && sphere.mass > object.mass
+                && sphere.position.distance(object.position) < sphere.radius
+        })
+        .min_by(|(_, a), (_, b)| a.radius.total_cmp(&b.radius))
+        .map(|(index, _)| index)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SoiEvent {
+    pub object: usize,
+    pub from: Option<usize>,
+    pub to: Option<usize>,
+    pub time: f32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct InfluenceTracker {
+    spheres: Vec<SphereOfInfluence>,
+    primaries: HashMap<usize, Option<usize>>,
+    history: VecDeque<SoiEvent>,
+}
+
+impl InfluenceTracker {
+    pub fn update(&mut self, objects: &ObjectPool, grav_const: f32, time: f32) -> Vec<SoiEvent> {
+        self.spheres = spheres_of_influence(objects, grav_const);
+        let assigned = assign_primaries(objects, &self.spheres);
+
+        let mut events = Vec::new();
+        for obj in objects.iter() {
+            let to = assigned.get(&obj.id).copied();
+            if let Some(from) = self.primaries.insert(obj.id, to)
+                && from != to
+            {
+                events.push(SoiEvent {
+                    object: obj.id,
+                    from,
+                    to,
+                    time,
+                });
+            }
+        }
+        let ids: HashSet<usize> = objects.iter().map(|obj| obj.id).collect();
+        self.primaries.retain(|id, _| ids.contains(id));
+
+        for event in &events {
+            if self.history.len() >= EVENT_HISTORY {
+                self.history.pop_front();
+            }
+            self.history.push_back(*event);
+        }
+
+        events
+    }
+
+    pub fn primary_of(&self, id: usize) -> Option<usize> {
+        self.primaries.get(&id).copied().flatten()
+    }
+
+    pub fn sphere_of(&self, body: usize) -> Option<&SphereOfInfluence> {
+        self.spheres.iter().find(|sphere| sphere.body == body)
+    }
+
+    pub fn spheres(&self) -> &[SphereOfInfluence] {
+        &self.spheres
+    }
+
+    pub fn history(&self) -> impl DoubleEndedIterator<Item = &SoiEvent> {
+        self.history.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.spheres.clear();
+        self.primaries.clear();
+        self.history.clear();
+    }
+}
@@ -1,8 +1,27 @@
+pub mod boundary;
 pub mod control;
+pub mod diagnostics;
+pub mod edit;
+pub mod encounter;
+pub mod export;
+pub mod frame;
+pub mod horizons;
+pub mod influence;
+pub mod maneuver;
 pub mod object;
+pub mod orbit;
 pub mod physics;
+pub mod prediction;
+pub mod recorder;
 pub mod renderer;
+pub mod scenario;
+#[cfg(feature = "scene")]
+pub mod scene;
 pub mod screen;
+pub mod spawn;
+pub mod timeline;
+pub mod trail;
+pub mod units;
 pub mod world;
 
 #[cfg(test)]
@@ -97,4 +116,1216 @@ mod tests {
 
         assert!(!ray.raycast(pos, 1.));
     }
+
+    use crate::physics::*;
+
+    #[test]
+    fn merge_conserves_angular_momentum() {
+        let a = Object::new(vec3(1., 0., 0.), vec3(0., 0., 1.), 2., 1., WHITE);
+        let b = Object::new(vec3(-1., 0., 0.), vec3(0., 0., -1.), 2., 1., WHITE);
+
+        let com = (a.mass * a.position + b.mass * b.position) / (a.mass + b.mass);
+        let orbital = a.mass * (a.position - com).cross(a.velocity)
+            + b.mass * (b.position - com).cross(b.velocity);
+
+        let spin = PhysicsHandler::merged_spin(&a, &b, 2.);
+        let merged_momentum = Object::sphere_inertia(4., 2.) * spin;
+
+        assert!((merged_momentum - orbital).length() < 1e-5);
+    }
+
+    #[test]
+    fn object_update_rotation() {
+        let mut obj = Object::default();
+        obj.update_pos(1.0);
+        assert_eq!(obj.orientation, Quat::IDENTITY);
+
+        obj.spin = vec3(0., 1., 0.);
+        obj.update_pos(1.0);
+        assert!((obj.orientation.to_scaled_axis() - vec3(0., 1., 0.)).length() < 1e-5);
+    }
+
+    use crate::boundary::*;
+
+    #[test]
+    fn boundary_reflect_and_wrap() {
+        let sphere = BoundaryShape::Sphere {
+            center: Vec3::ZERO,
+            radius: 10.,
+        };
+        let mut obj = Object::new(vec3(12., 0., 0.), vec3(1., 0., 0.), 1., 1., WHITE);
+        let event = Boundary::new(sphere, BoundaryPolicy::Reflect).apply(&mut obj);
+
+        assert_eq!(event, Some(BoundaryEvent::Reflected(0)));
+        assert_eq!(obj.position, vec3(10., 0., 0.));
+        assert_eq!(obj.velocity, vec3(-1., 0., 0.));
+
+        let cube = BoundaryShape::Box {
+            min: Vec3::splat(-5.),
+            max: Vec3::splat(5.),
+        };
+        let mut obj = Object::with_pos(vec3(6., 0., -7.));
+        Boundary::new(cube, BoundaryPolicy::Wrap).apply(&mut obj);
+
+        assert_eq!(obj.position, vec3(-4., 0., 3.));
+
+        let mut objects = ObjectPool::new();
+        objects.push(Object::new(Vec3::ZERO, Vec3::ZERO, 100., 1., WHITE));
+        let id = objects.push(Object::with_pos(vec3(12., 0., 0.)));
+        let mut physics_handler = PhysicsHandler::default();
+        physics_handler.set_domain(Domain::new(
+            vec![Boundary::new(sphere, BoundaryPolicy::Freeze)],
+            None,
+            false,
+        ));
+        physics_handler.update(&mut objects, 2.);
+
+        let frozen = objects.get(id).unwrap();
+        assert!(frozen.frozen);
+        assert_eq!(frozen.position, vec3(10., 0., 0.));
+        assert_eq!(frozen.velocity, Vec3::ZERO);
+    }
+
+    #[test]
+    fn escape_detection() {
+        let mut objects = ObjectPool::new();
+        objects.push(Object::new(Vec3::ZERO, Vec3::ZERO, 100., 1., WHITE));
+        let bound = objects.push(Object::new(
+            vec3(50., 0., 0.),
+            vec3(0., 0., 1.),
+            1.,
+            1.,
+            WHITE,
+        ));
+        let unbound = objects.push(Object::new(
+            vec3(-50., 0., 0.),
+            vec3(-10., 0., 0.),
+            1.,
+            1.,
+            WHITE,
+        ));
+
+        let escaped = Domain::find_escaped(&objects, 1., 20.);
+
+        assert!(!escaped.contains(&bound));
+        assert!(escaped.contains(&unbound));
+    }
+
+    #[test]
+    fn boundary_log_keeps_latest_events() {
+        let mut log = BoundaryLog::new(2);
+        log.record(
+            1.,
+            &[BoundaryEvent::Escaped(4), BoundaryEvent::Reflected(2)],
+        );
+        log.record(2., &[BoundaryEvent::Escaped(4)]);
+        log.record(3., &[BoundaryEvent::Wrapped(7)]);
+
+        let entries: Vec<(f32, BoundaryEvent)> = log.iter().copied().collect();
+        assert_eq!(
+            entries,
+            vec![
+                (2., BoundaryEvent::Escaped(4)),
+                (3., BoundaryEvent::Wrapped(7))
+            ]
+        );
+    }
+
+    #[cfg(feature = "scene")]
+    #[test]
+    fn scene_ron_round_trip() {
+        use crate::scene::*;
+
+        let mut objects = ObjectPool::new();
+        objects.push(Object::new(vec3(1., 2., 3.), vec3(0., 0., 1.), 5., 2., RED));
+
+        let scene = Scene {
+            grav_const: 2.,
+            timestep: 0.1,
+            units: SceneUnits::from(&Units::astronomical(50., 1., 2.)),
+            camera: SceneCamera {
+                position: [0., 10., -15.],
+                target: [0., 0., 0.],
+                up: [0., 1., 0.],
+                fovy: 1.,
+            },
+            objects: objects.iter().map(SceneObject::from).collect(),
+        };
+
+        let text = scene.to_ron().unwrap();
+        let loaded = Scene::from_ron(&text).unwrap();
+        assert_eq!(loaded, scene);
+        assert_eq!(Units::from(&loaded.units), Units::astronomical(50., 1., 2.));
+        assert!(Scene::from_ron(&text.replace("Astronomical", "Imperial")).is_err());
+
+        let pool = loaded.build_pool();
+        let obj = pool.iter().next().unwrap();
+        assert_eq!(obj.position, vec3(1., 2., 3.));
+        assert_eq!(obj.color, RED);
+    }
+
+    use crate::recorder::*;
+
+    #[test]
+    fn recorder_rotates_files() {
+        let dir = std::env::temp_dir().join(format!("gravity-sim-recorder-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut objects = ObjectPool::new();
+        objects.push(Object::default());
+        objects.push(Object::with_pos(vec3(10., 0., 0.)));
+
+        let mut physics_handler = PhysicsHandler::default();
+        let mut recorder = Recorder::new(&dir, "test", 2, 3);
+
+        for _ in 0..4 {
+            physics_handler.step(&mut objects);
+            recorder.sample(&physics_handler, &objects).unwrap();
+        }
+        recorder.flush().unwrap();
+
+        let first = std::fs::read_to_string(dir.join("test_0000.csv")).unwrap();
+        let second = std::fs::read_to_string(dir.join("test_0001.csv")).unwrap();
+
+        assert_eq!(first.lines().next(), Some(CSV_HEADER));
+        assert_eq!(first.lines().count(), 4);
+        assert_eq!(second.lines().count(), 2);
+
+        let mut next_run = Recorder::new(&dir, "test", 2, 3);
+        next_run.record(0., &objects).unwrap();
+        next_run.flush().unwrap();
+        assert_eq!(next_run.current_path(), dir.join("test_0002.csv"));
+        assert_eq!(
+            std::fs::read_to_string(dir.join("test_0000.csv")).unwrap(),
+            first
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    use crate::edit::*;
+
+    #[test]
+    fn edit_history_undo_redo() {
+        let mut objects = ObjectPool::new();
+        let mut history = EditHistory::default();
+
+        let id = history.spawn(&mut objects, Object::default());
+        history.execute(
+            &mut objects,
+            EditCommand::Move {
+                id,
+                from: Vec3::ZERO,
+                to: vec3(3., 0., 0.),
+            },
+        );
+        history.delete(&mut objects, id);
+        assert!(objects.get(id).is_none());
+
+        assert!(history.undo(&mut objects));
+        assert_eq!(objects.get(id).unwrap().position, vec3(3., 0., 0.));
+
+        assert!(history.undo(&mut objects));
+        assert_eq!(objects.get(id).unwrap().position, Vec3::ZERO);
+
+        assert!(history.undo(&mut objects));
+        assert!(objects.get(id).is_none());
+        assert!(!history.undo(&mut objects));
+
+        assert!(history.redo(&mut objects));
+        assert_eq!(objects.get(id).unwrap().id, id);
+        assert_eq!(objects.push(Object::default()), id + 1);
+    }
+
+    #[test]
+    fn edit_history_group_membership() {
+        let mut objects = ObjectPool::new();
+        let mut history = EditHistory::default();
+
+        let a = objects.push(Object::with_pos(vec3(1., 0., 0.)));
+        let b = objects.push(Object::with_pos(vec3(-1., 0., 0.)));
+        history.execute(
+            &mut objects,
+            EditCommand::Join {
+                group: "moons".into(),
+                id: a,
+            },
+        );
+        history.execute(
+            &mut objects,
+            EditCommand::Join {
+                group: "moons".into(),
+                id: b,
+            },
+        );
+
+        let before: Vec<Object> = objects.iter().cloned().collect();
+        objects.translate_group("moons", vec3(0., 0., 5.));
+        history.record(EditCommand::Batch(
+            before
+                .into_iter()
+                .map(|before| {
+                    let after = objects.get(before.id).unwrap().clone();
+                    EditCommand::Transform { before, after }
+                })
+                .collect(),
+        ));
+
+        let moons = objects.group_ids("moons");
+        history.delete_all(&mut objects, &moons);
+        assert!(objects.is_empty());
+        assert_eq!(objects.group_names().count(), 0);
+
+        assert!(history.undo(&mut objects));
+        assert_eq!(objects.group_ids("moons").len(), 2);
+        assert_eq!(objects.get(a).unwrap().position, vec3(1., 0., 5.));
+
+        assert!(history.undo(&mut objects));
+        assert_eq!(objects.get(a).unwrap().position, vec3(1., 0., 0.));
+
+        assert!(history.undo(&mut objects));
+        assert_eq!(objects.groups_of(b), Vec::<String>::new());
+    }
+
+    use crate::timeline::*;
+
+    #[test]
+    fn timeline_scrub_and_branch() {
+        let mut objects = ObjectPool::new();
+        objects.push(Object::new(Vec3::ZERO, vec3(1., 0., 0.), 1., 1., WHITE));
+
+        let mut physics_handler = PhysicsHandler::default();
+        let mut timeline = Timeline::new(1, usize::MAX);
+
+        for _ in 0..5 {
+            physics_handler.step(&mut objects);
+            timeline.sample(&physics_handler, &objects);
+        }
+
+        timeline.pause(&physics_handler, &objects);
+        assert_eq!(timeline.len(), 5);
+
+        let latest = objects.clone();
+        timeline.scrub(-2, &mut objects, &mut physics_handler);
+        assert_eq!(physics_handler.get_step_count(), 3);
+        assert!(!timeline.is_edited(&objects));
+
+        assert!(timeline.resume(&mut objects, &mut physics_handler));
+        assert_eq!(timeline.len(), 5);
+        assert_eq!(physics_handler.get_step_count(), 5);
+        assert_eq!(objects, latest);
+
+        timeline.pause(&physics_handler, &objects);
+        timeline.scrub(-2, &mut objects, &mut physics_handler);
+
+        objects.push(Object::default());
+        assert!(timeline.is_edited(&objects));
+        timeline.branch(&objects);
+        assert_eq!(timeline.len(), 3);
+
+        assert!(!timeline.resume(&mut objects, &mut physics_handler));
+        physics_handler.step(&mut objects);
+        timeline.sample(&physics_handler, &objects);
+        assert_eq!(timeline.len(), 4);
+        assert_eq!(physics_handler.get_step_count(), 4);
+    }
+
+    #[test]
+    fn timeline_memory_budget() {
+        let mut objects = ObjectPool::new();
+        objects.push(Object::default());
+
+        let mut physics_handler = PhysicsHandler::default();
+        let budget = 3 * (size_of::<Snapshot>() + size_of::<Object>());
+        let mut timeline = Timeline::new(1, budget);
+
+        for _ in 0..10 {
+            physics_handler.step(&mut objects);
+            timeline.sample(&physics_handler, &objects);
+        }
+
+        assert_eq!(timeline.len(), 3);
+    }
+
+    use crate::scenario::*;
+
+    #[test]
+    fn scenarios_are_balanced() {
+        for scenario in Scenario::ALL {
+            let objects = scenario.generate(1.);
+            assert!(!objects.is_empty());
+            assert_eq!(Scenario::from_name(scenario.name()), Some(scenario));
+
+            let total_mass: f32 = objects.iter().map(|obj| obj.mass).sum();
+            let momentum: Vec3 = objects.iter().map(|obj| obj.mass * obj.velocity).sum();
+            let com: Vec3 = objects
+                .iter()
+                .map(|obj| obj.mass * obj.position)
+                .sum::<Vec3>()
+                / total_mass;
+
+            assert!(
+                momentum.length() < 1e-2,
+                "{} has net momentum",
+                scenario.name()
+            );
+            assert!(com.length() < 1e-2, "{} is off-center", scenario.name());
+        }
+    }
+
+    use crate::horizons::*;
+
+    const EARTH_VECTORS: &str = "\
+*******************************************************************************
+ Revised: April 12, 2021                 Earth                              399
+ Vol. Mean Radius (km)    = 6371.01+-0.02   Mass x10^24 (kg)= 5.97219+-0.0006
+ GM, km^3/s^2             = 398600.435436   Mass ratio (Sun/Earth) = 332946.0487
+*******************************************************************************
+Target body name: Earth (399)                     {source: DE441}
+Center body name: Sun (10)                        {source: DE441}
+Output units    : AU-D
+*******************************************************************************
+$$SOE
+2460000.500000000 = A.D. 2023-Feb-25 00:00:00.0000 TDB
+ X =-9.000000000000000E-01 Y = 4.000000000000000E-01 Z = 1.000000000000000E-05
+ VX=-7.000000000000000E-03 VY=-1.600000000000000E-02 VZ= 1.000000000000000E-07
+ LT= 5.700000000000000E-03 RG= 9.800000000000000E-01 RR=-1.000000000000000E-04
+$$EOE
+*******************************************************************************
+";
+
+    #[test]
+    fn horizons_parse_vector_table() {
+        let table = HorizonsTable::parse(EARTH_VECTORS).unwrap();
+
+        assert_eq!(table.name, "Earth");
+        assert_eq!(table.units, HorizonsUnits::AuDay);
+        assert_eq!(table.gm, Some(398600.435436));
+        assert_eq!(table.radius_km, Some(6371.01));
+        assert_eq!(table.records.len(), 1);
+        assert_eq!(table.records[0].julian_date, 2460000.5);
+        assert_eq!(table.records[0].position.x, -0.9);
+        assert_eq!(table.records[0].velocity.y, -0.016);
+
+        let scale = HorizonsScale::default();
+        let obj = table.to_object(0, &scale, 0.).unwrap();
+        assert_eq!(obj.name.as_deref(), Some("Earth"));
+        assert!((obj.position - vec3(-45., 0.0005, -20.)).length() < 1e-4);
+        assert!(obj.position.cross(obj.velocity).y > 0.);
+
+        let sun_mass = scale.mass_per_solar_mass() as f32;
+        assert!((obj.mass / sun_mass - 3.003e-6).abs() < 1e-8);
+    }
+
+    #[test]
+    fn horizons_parse_csv_table() {
+        let text = "Target body name: Mars (499)\nOutput units    : KM-S\n$$SOE\n\
+            2460000.5, A.D. 2023-Feb-25 00:00:00.0000, 1.0E+08, 2.0E+08, 3.0E+06, -20.0, 10.0, 1.0,\n$$EOE\n";
+        let table = HorizonsTable::parse(text).unwrap();
+
+        assert_eq!(table.name, "Mars");
+        assert_eq!(table.units, HorizonsUnits::KmSec);
+        assert_eq!(table.records[0].velocity.x, -20.);
+        assert!(
+            (table.velocity_au_per_day(&table.records[0]).x - -20. * DAY_S / AU_KM).abs() < 1e-12
+        );
+        assert!(HorizonsTable::parse("no table").is_err());
+
+        let sun = " GM, km^3/s^2          = 132712440041.93938  Mass, 10^24 kg        = ~1988500\n \
+            Solar radius (IAU)    = 696000 km           Mean density, g/cm^3  = 1.408\n\
+            Target body name: Sun (10)\nOutput units    : AU-D\n$$SOE\n\
+            2460000.5, A.D. 2023-Feb-25 00:00:00.0000, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,\n$$EOE\n";
+        let table = HorizonsTable::parse(sun).unwrap();
+        assert_eq!(table.radius_km, Some(696000.));
+        let scale = HorizonsScale::default();
+        assert_eq!(
+            table.to_object(0, &scale, 0.).unwrap().radius,
+            scale.max_radius
+        );
+    }
+
+    use crate::units::*;
+
+    #[test]
+    fn units_scale_grav_const() {
+        assert_eq!(Units::natural().grav_const(), 1.);
+        assert_eq!(Units::natural().format(Quantity::Length, 2.), "2.000");
+
+        let units = Units::astronomical(50., 1., 1.);
+        assert!((units.grav_const() - 1.).abs() < 1e-5);
+        assert!((units.to_display(Quantity::Length, 50.) - 1.).abs() < 1e-9);
+        assert!((units.to_display(Quantity::Time, 365.25) - 365.25).abs() < 1e-3);
+        assert_eq!(units.label(Quantity::Velocity), "AU d^-1");
+
+        let si = Units::si(1_000., 1., 2.);
+        assert!((si.grav_const() - 2.).abs() < 1e-5);
+        assert!((si.to_si(Quantity::Velocity, 3.) - 3_000.).abs() < 1e-6);
+        assert!((si.from_display(Quantity::Length, 5_000.) - 5.).abs() < 1e-6);
+        assert_eq!(si.label(Quantity::Energy), "J");
+
+        let recorder = crate::recorder::Recorder::new("unused", "unused", 1, 1).with_units(units);
+        assert!(recorder.header().starts_with("time [d],id,x [AU]"));
+    }
+
+    #[test]
+    fn object_group_transforms() {
+        let mut objects = ObjectPool::new();
+        let a = objects.push(Object::with_pos(vec3(1., 0., 0.)));
+        let b = objects.push(Object::with_pos(vec3(-1., 0., 0.)));
+        let outsider = objects.push(Object::with_pos(vec3(10., 0., 0.)));
+        objects.add_to_group("moons", a);
+        objects.add_to_group("moons", b);
+
+        objects.translate_group("moons", vec3(0., 0., 5.));
+        objects.boost_group("moons", vec3(0., 1., 0.));
+        objects.scale_group("moons", 2.);
+        assert_eq!(objects.get(a).unwrap().position, vec3(2., 0., 5.));
+        assert_eq!(objects.get(b).unwrap().velocity, vec3(0., 1., 0.));
+        assert_eq!(objects.get(outsider).unwrap().position, vec3(10., 0., 0.));
+
+        objects.rotate_group("moons", Quat::from_rotation_y(std::f32::consts::PI));
+        assert!((objects.get(a).unwrap().position - vec3(-2., 0., 5.)).length() < 1e-5);
+
+        let copies = objects.duplicate_group("moons", "copies", vec3(0., 3., 0.));
+        assert_eq!(copies.len(), 2);
+        assert_eq!(objects.groups_of(copies[0]), vec!["copies".to_string()]);
+
+        let removed = objects.delete_group("moons");
+        assert_eq!(removed.len(), 2);
+        assert_eq!(objects.len(), 3);
+        assert!(objects.group_ids("moons").is_empty());
+    }
+
+    #[test]
+    fn object_pool_barycentric_frame() {
+        let mut objects = ObjectPool::new();
+        objects.push(Object::new(
+            vec3(10., 0., 0.),
+            vec3(1., 0., 0.),
+            3.,
+            1.,
+            WHITE,
+        ));
+        objects.push(Object::new(
+            vec3(20., 4., 0.),
+            vec3(0., 0., 2.),
+            1.,
+            1.,
+            WHITE,
+        ));
+
+        assert_eq!(objects.center_of_mass(), Some(vec3(12.5, 1., 0.)));
+
+        objects.to_barycentric_frame();
+        assert!(objects.center_of_mass().unwrap().length() < 1e-5);
+        assert!(objects.total_momentum().length() < 1e-5);
+        assert_eq!(ObjectPool::new().center_of_mass(), None);
+    }
+
+    use crate::frame::*;
+
+    #[test]
+    fn rotating_frame_binary_is_stationary() {
+        let mut objects = ObjectPool::new();
+        let speed = 0.5f32.sqrt();
+        let a = objects.push(Object::new(
+            vec3(5., 0., 0.),
+            vec3(0., 0., speed),
+            10.,
+            1.,
+            WHITE,
+        ));
+        let b = objects.push(Object::new(
+            vec3(-5., 0., 0.),
+            vec3(0., 0., -speed),
+            10.,
+            1.,
+            WHITE,
+        ));
+        let probe = objects.push(Object::new(
+            vec3(3., 1., 7.),
+            vec3(0.2, 0., -0.1),
+            1e-3,
+            0.1,
+            WHITE,
+        ));
+
+        let transform = RotatingFrame::new(a, b).transform(&objects).unwrap();
+        let framed = transform.pool_to_frame(&objects);
+
+        assert!(framed.get(a).unwrap().velocity.length() < 1e-5);
+        assert!((framed.get(a).unwrap().position - vec3(-5., 0., 0.)).length() < 1e-5);
+
+        let round_trip = transform.object_to_world(framed.get(probe).unwrap());
+        assert!((round_trip.position - vec3(3., 1., 7.)).length() < 1e-4);
+        assert!((round_trip.velocity - vec3(0.2, 0., -0.1)).length() < 1e-4);
+
+        let mut physics_handler = PhysicsHandler::default();
+        physics_handler.set_frame_rotation(Some(transform.frame_angular_velocity()));
+        let mut framed = framed;
+        for _ in 0..10 {
+            physics_handler.step(&mut framed);
+        }
+
+        assert!((framed.get(a).unwrap().position - vec3(-5., 0., 0.)).length() < 0.1);
+        assert!((framed.get(b).unwrap().position - vec3(5., 0., 0.)).length() < 0.1);
+    }
+
+    #[test]
+    fn test_particles_exert_no_gravity() {
+        let mut objects = ObjectPool::new();
+        let planet = objects.push(Object::new(Vec3::ZERO, Vec3::ZERO, 10., 1., WHITE));
+        let particle = objects.push(Object::particle(vec3(10., 0., 0.), Vec3::ZERO, WHITE));
+        let doomed = objects.push(Object::particle(vec3(0.5, 0., 0.), Vec3::ZERO, WHITE));
+
+        let physics_handler = PhysicsHandler::default();
+        physics_handler.update_objects(&mut objects, 0.2);
+
+        assert_eq!(objects.get(planet).unwrap().velocity, Vec3::ZERO);
+        assert_eq!(objects.get(planet).unwrap().mass, 10.);
+        assert!(objects.get(particle).unwrap().velocity.x < 0.);
+        assert!(objects.get(doomed).is_none());
+    }
+
+    #[test]
+    fn collisions_merge_at_center_of_mass() {
+        let mut objects = ObjectPool::new();
+        let heavy = objects.push(Object::new(Vec3::ZERO, vec3(0., 0., 1.), 3., 1., WHITE));
+        let light = objects.push(Object::new(vec3(1.5, 0., 0.), Vec3::ZERO, 1., 1., RED));
+        let far = objects.push(Object::particle(vec3(50., 0., 0.), Vec3::ZERO, WHITE));
+        let near = objects.push(Object::particle(vec3(50.1, 0., 0.), Vec3::ZERO, WHITE));
+        objects.add_to_group("pair", heavy);
+
+        let collisions = PhysicsHandler::default().handle_collisions(&mut objects);
+
+        assert!(collisions.contains(&Collision {
+            object: light,
+            other: heavy
+        }));
+        assert!(objects.get(heavy).is_none() && objects.get(light).is_none());
+        assert!(objects.get(far).is_some() && objects.get(near).is_some());
+        assert_eq!(objects.len(), 3);
+
+        let merged = objects.iter().find(|obj| !obj.test_particle).unwrap();
+        assert_eq!(merged.mass, 4.);
+        assert_eq!(merged.position, vec3(0.375, 0., 0.));
+        assert_eq!(merged.velocity, vec3(0., 0., 0.75));
+        assert_eq!(objects.group_ids("pair"), vec![merged.id]);
+    }
+
+    use crate::prediction::*;
+    use std::time::Duration;
+
+    #[test]
+    fn trajectory_predictor_caches_and_extends() {
+        let mut objects = ObjectPool::new();
+        objects.push(Object::new(Vec3::ZERO, Vec3::ZERO, 100., 2., WHITE));
+        let moon = objects.push(Object::new(
+            vec3(20., 0., 0.),
+            vec3(0., 0., 2.),
+            1.,
+            1.,
+            WHITE,
+        ));
+        let mut physics_handler = PhysicsHandler::default();
+
+        let mut stepped = objects.clone();
+        let mut stepped_physics = physics_handler.clone();
+        let mut expected = Vec::new();
+        for i in 0..1000 {
+            stepped_physics.step(&mut stepped);
+            if i % 2 == 0 {
+                expected.push(stepped.get(moon).unwrap().position);
+            }
+        }
+
+        let mut predictor = TrajectoryPredictor::new(500, 2).with_budget(Duration::ZERO);
+        let mut frames = 0;
+        while !predictor.is_current(&objects, &physics_handler, None, None) {
+            let partial = predictor.update(&objects, &physics_handler, None, None);
+            assert!(partial.get(&moon).is_none_or(|traj| traj.len() <= 500));
+            frames += 1;
+        }
+        assert!(frames > 1);
+        assert_eq!(
+            predictor
+                .get(moon)
+                .unwrap()
+                .iter()
+                .copied()
+                .collect::<Vec<_>>(),
+            expected
+        );
+
+        predictor.update(&objects, &physics_handler, None, None);
+        assert!(predictor.is_idle());
+
+        for _ in 0..5 {
+            physics_handler.step(&mut objects);
+            stepped_physics.step(&mut stepped);
+        }
+        predictor.update(&objects, &physics_handler, None, None);
+        assert!(predictor.is_current(&objects, &physics_handler, None, None));
+
+        let traj = predictor.get(moon).unwrap();
+        assert_eq!(traj.first(), Some(&expected[1]));
+        assert_eq!(traj.len(), 502);
+        assert!(traj.time(0).unwrap() <= physics_handler.get_sim_time());
+        assert_eq!(traj.time(1).unwrap(), physics_handler.get_sim_time());
+        assert_eq!(
+            traj.iter().last(),
+            Some(&stepped.get(moon).unwrap().position)
+        );
+
+        let before = objects.generation();
+        objects.get_mut(moon).unwrap();
+        assert_eq!(objects.generation(), before);
+
+        let mut moved = objects.get(moon).unwrap().clone();
+        moved.velocity.z = 3.;
+        EditCommand::Edit {
+            before: objects.get(moon).unwrap().clone(),
+            after: moved,
+        }
+        .apply(&mut objects);
+        predictor.update(&objects, &physics_handler, None, None);
+        assert!(!predictor.is_current(&objects, &physics_handler, None, None));
+    }
+
+    #[test]
+    fn trajectory_predictor_sees_pushed_objects() {
+        fn predict(
+            predictor: &mut TrajectoryPredictor,
+            objects: &ObjectPool,
+            physics_handler: &PhysicsHandler,
+        ) -> HashMap<usize, Trajectory> {
+            while !predictor.is_current(objects, physics_handler, None, None) {
+                predictor.update(objects, physics_handler, None, None);
+            }
+            predictor.trajectories().clone()
+        }
+
+        let mut objects = ObjectPool::new();
+        objects.push(Object::new(Vec3::ZERO, Vec3::ZERO, 100., 2., WHITE));
+        let physics_handler = PhysicsHandler::default();
+        let mut predictor = TrajectoryPredictor::new(100, 2);
+
+        let mut orbiting = objects.get_all_in_area(Vec3::ZERO, 500.);
+        let id = orbiting.push(Object::new(
+            vec3(20., 0., 0.),
+            vec3(0., 0., 2.),
+            1.,
+            1.,
+            WHITE,
+        ));
+        let mut falling = objects.get_all_in_area(Vec3::ZERO, 500.);
+        falling.push(Object::new(
+            vec3(20., 0., 0.),
+            vec3(0., 0., -0.5),
+            1.,
+            1.,
+            WHITE,
+        ));
+
+        let orbit = predict(&mut predictor, &orbiting, &physics_handler);
+        let fall = predict(&mut predictor, &falling, &physics_handler);
+        assert_ne!(orbit[&id].iter().last(), fall[&id].iter().last());
+
+        falling.remove(id);
+        let removed = predict(&mut predictor, &falling, &physics_handler);
+        assert!(!removed.contains_key(&id));
+    }
+
+    #[test]
+    fn trajectories_relative_to_reference() {
+        let mut objects = ObjectPool::new();
+        let planet = objects.push(Object::new(Vec3::ZERO, vec3(3., 0., 0.), 100., 2., WHITE));
+        let moon = objects.push(Object::new(
+            vec3(0., 0., 20.),
+            vec3(3. - (100f32 / 20.).sqrt(), 0., 0.),
+            0.001,
+            0.5,
+            WHITE,
+        ));
+        let physics_handler = PhysicsHandler::default();
+
+        let trajectories =
+            objects.calculate_trajectories(&physics_handler, 200, 2, None, Some(planet));
+        assert_eq!(trajectories[&moon].reference(), Some(planet));
+        assert!(
+            trajectories[&planet]
+                .iter()
+                .all(|point| point.length() < 1e-3)
+        );
+        assert!(
+            trajectories[&moon]
+                .iter()
+                .all(|point| (point.length() - 20.).abs() < 1.)
+        );
+
+        let moon_obj = objects.get(moon).unwrap().clone();
+        let traj =
+            moon_obj.calculate_trajectory(&objects, &physics_handler, 200, 2, None, Some(planet));
+        assert_eq!(
+            traj.iter().collect::<Vec<_>>(),
+            trajectories[&moon].iter().collect::<Vec<_>>()
+        );
+    }
+
+    use crate::encounter::*;
+
+    #[test]
+    fn encounters_find_closest_approach() {
+        let mut objects = ObjectPool::new();
+        let a = objects.push(Object::new(
+            vec3(-10., 0., 0.),
+            vec3(1., 0., 0.),
+            1.,
+            0.5,
+            WHITE,
+        ));
+        let b = objects.push(Object::new(
+            vec3(10., 0., 5.),
+            vec3(-1., 0., 0.),
+            1.,
+            0.5,
+            WHITE,
+        ));
+        let physics_handler = PhysicsHandler::new(0., 0.2);
+
+        let trajectories = objects.calculate_trajectories(&physics_handler, 100, 1, None, None);
+        let encounters = find_encounters(&trajectories[&a], &trajectories[&b]);
+
+        assert_eq!(encounters.len(), 1);
+        assert!((encounters[0].time - 10.).abs() < 0.3);
+        assert!((encounters[0].distance - 5.).abs() < 0.1);
+        assert_eq!(closest_approach(&encounters), Some(encounters[0]));
+        assert_eq!(
+            trajectories[&a].sample_at(encounters[0].time),
+            Some(encounters[0].first)
+        );
+
+        let mut sparse = Trajectory::new();
+        sparse.push_at(Vec3::ZERO, 0.).push_at(Vec3::ZERO, 10.);
+        let mut dense = Trajectory::new();
+        for i in 0..=10 {
+            let time = i as f32;
+            dense.push_at(vec3((time - 5.).abs() + 1., 0., 0.), time);
+        }
+        let closest = closest_approach(&find_encounters(&sparse, &dense)).unwrap();
+        assert_eq!((closest.time, closest.distance), (5., 1.));
+    }
+
+    #[test]
+    fn trajectory_termination_reasons() {
+        let mut objects = ObjectPool::new();
+        let small = objects.push(Object::new(
+            vec3(-10., 0., 0.),
+            vec3(1., 0., 0.),
+            1.,
+            1.,
+            WHITE,
+        ));
+        let large = objects.push(Object::new(
+            vec3(10., 0., 0.),
+            vec3(-1., 0., 0.),
+            2.,
+            1.,
+            WHITE,
+        ));
+        let runaway = objects.push(Object::particle(vec3(0., 0., 40.), vec3(0., 0., 5.), WHITE));
+
+        let mut physics_handler = PhysicsHandler::new(0., 0.2);
+        physics_handler.set_clock(100., 500);
+        physics_handler.set_domain(Domain::new(
+            vec![Boundary::new(
+                BoundaryShape::Sphere {
+                    center: Vec3::ZERO,
+                    radius: 50.,
+                },
+                BoundaryPolicy::Despawn,
+            )],
+            None,
+            false,
+        ));
+
+        let trajectories = objects.calculate_trajectories(&physics_handler, 100, 1, None, None);
+
+        match trajectories[&small].termination() {
+            Termination::Collision { other, time } => {
+                assert_eq!(other, large);
+                assert!((time - 109.5).abs() < 0.5);
+            }
+            termination => panic!("unexpected termination {termination:?}"),
+        }
+        assert!(matches!(
+            trajectories[&large].termination(),
+            Termination::Collision { other, .. } if other == small
+        ));
+        match trajectories[&runaway].termination() {
+            Termination::LeftDomain { time } => assert!((time - 102.).abs() < 0.3),
+            termination => panic!("unexpected termination {termination:?}"),
+        }
+        assert!(trajectories.values().all(|traj| traj.duration() <= 120.));
+    }
+
+    use crate::orbit::*;
+
+    #[test]
+    fn orbital_elements_from_state() {
+        let mut objects = ObjectPool::new();
+        let sun = objects.push(Object::new(Vec3::ZERO, Vec3::ZERO, 1000., 5., WHITE));
+        objects.push(Object::new(vec3(500., 0., 0.), Vec3::ZERO, 10., 1., WHITE));
+
+        let mu: f32 = 1000.;
+        let speed = (mu * 1.5 / 10.).sqrt();
+        let comet = Object::particle(vec3(10., 0., 0.), vec3(0., 0., speed), WHITE);
+        let primary = dominant_body(&objects, &comet).unwrap();
+        assert_eq!(primary.id, sun);
+
+        let elements = OrbitalElements::of(&comet, primary, 1.).unwrap();
+        assert!(elements.is_bound());
+        assert!((elements.eccentricity - 0.5).abs() < 1e-3);
+        assert!((elements.semi_major_axis - 20.).abs() < 1e-2);
+        assert!(elements.periapsis.distance(vec3(10., 0., 0.)) < 1e-2);
+        assert!(elements.apoapsis.unwrap().distance(vec3(-30., 0., 0.)) < 1e-1);
+        let period = std::f32::consts::TAU * (20f32.powi(3) / mu).sqrt();
+        assert!((elements.period.unwrap() - period).abs() < 1e-2);
+
+        let escaping = Object::particle(vec3(10., 0., 0.), vec3(0., 0., speed * 2.), WHITE);
+        let elements = OrbitalElements::of(&escaping, primary, 1.).unwrap();
+        assert!(!elements.is_bound());
+        assert!(elements.apoapsis.is_none() && elements.period.is_none());
+    }
+
+    use crate::trail::*;
+
+    #[test]
+    fn trails_are_bounded_and_fade() {
+        let mut objects = ObjectPool::new();
+        let mover = objects.push(Object::particle(Vec3::ZERO, vec3(1., 0., 0.), WHITE));
+        let doomed = objects.push(Object::particle(vec3(0., 0., 5.), Vec3::ZERO, WHITE));
+        let mut physics_handler = PhysicsHandler::new(0., 0.2);
+        let mut trails = Trails::new(4, 2);
+
+        for _ in 0..20 {
+            physics_handler.step(&mut objects);
+            trails.sample(&physics_handler, &objects, None);
+        }
+
+        let trail = trails.get(mover).unwrap();
+        assert_eq!(trail.len(), 4);
+        let points: Vec<Vec3> = trail.iter().copied().collect();
+        assert!((points[3].x - 4.).abs() < 1e-4);
+        assert!((points[3].x - points[2].x - 0.4).abs() < 1e-4);
+        assert!(trail.alpha(0) < trail.alpha(3));
+        assert_eq!(trail.alpha(3), 1.);
+
+        objects.remove(doomed);
+        physics_handler.step(&mut objects);
+        physics_handler.step(&mut objects);
+        trails.sample(&physics_handler, &objects, None);
+        assert!(trails.get(doomed).is_none());
+
+        trails.set_mode(TrailMode::Off);
+        assert!(trails.get(mover).is_none());
+    }
+
+    use crate::export::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn export_trajectories_csv_and_obj() {
+        let mut first = Trajectory::new();
+        first
+            .push_at(vec3(0., 0., 0.), 0.5)
+            .push_at(vec3(1., 2., 3.), 1.);
+        let mut second = Trajectory::new();
+        second.push_at(vec3(4., 5., 6.), 0.5);
+        let trajectories = HashMap::from([(3, second), (1, first)]);
+
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &trajectories).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(
+            csv.lines().collect::<Vec<_>>(),
+            [
+                TRAJECTORY_CSV_HEADER,
+                "1,0.5,0,0,0",
+                "1,1,1,2,3",
+                "3,0.5,4,5,6"
+            ]
+        );
+
+        let mut obj = Vec::new();
+        write_obj(&mut obj, &trajectories).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert_eq!(
+            obj.lines().collect::<Vec<_>>(),
+            [
+                "o object_1",
+                "v 0 0 0",
+                "v 1 2 3",
+                "l 1 2",
+                "o object_3",
+                "v 4 5 6"
+            ]
+        );
+
+        let dir = std::env::temp_dir().join(format!("gravity-sim-export-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let paths = export_per_object(&dir, "predicted", ExportFormat::Obj, &trajectories).unwrap();
+        assert_eq!(
+            paths,
+            [dir.join("predicted_1.obj"), dir.join("predicted_3.obj")]
+        );
+        assert!(
+            std::fs::read_to_string(&paths[1])
+                .unwrap()
+                .starts_with("o object_3")
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn lagrange_points_are_equilibria() {
+        let mut objects = ObjectPool::new();
+        let sun = objects.push(Object::new(Vec3::ZERO, Vec3::ZERO, 1000., 5., WHITE));
+        let speed = (1010f32 / 100.).sqrt();
+        let planet = objects.push(Object::new(
+            vec3(100., 0., 0.),
+            vec3(0., 0., speed),
+            10.,
+            1.,
+            WHITE,
+        ));
+
+        let system = LagrangeSystem::new(&objects, planet, sun, 1.).unwrap();
+        assert_eq!((system.primary, system.secondary), (sun, planet));
+        assert!(system.primary_hill_radius.is_none());
+
+        let hill = 100. * (10f32 / 3000.).cbrt();
+        assert!((system.hill_radius - hill).abs() < 0.1);
+        assert!((system.points[0].x - (100. - hill)).abs() < 1.);
+        assert!((system.points[1].x - (100. + hill)).abs() < 1.);
+        assert!((system.points[2].x + 100.).abs() < 1.);
+
+        for point in &system.points[3..] {
+            assert!((point.distance(Vec3::ZERO) - 100.).abs() < 1.5);
+            assert!((point.distance(vec3(100., 0., 0.)) - 100.).abs() < 1.5);
+            assert_eq!(point.y, 0.);
+        }
+        assert!(system.points[3].z * system.points[4].z < 0.);
+    }
+
+    use crate::maneuver::*;
+
+    #[test]
+    fn maneuver_nodes_are_predicted_and_executed() {
+        let mut objects = ObjectPool::new();
+        let sun = objects.push(Object::new(Vec3::ZERO, Vec3::ZERO, 1000., 5., WHITE));
+        let speed = (1000f32 / 50.).sqrt();
+        let probe = objects.push(Object::particle(
+            vec3(50., 0., 0.),
+            vec3(0., 0., speed),
+            WHITE,
+        ));
+        let mut physics_handler = PhysicsHandler::new(1., 0.2);
+
+        let node = ManeuverNode::new(probe, 2.).with_delta_v(1., 0., 0.);
+        let expected = node.delta_v(&objects).unwrap();
+        assert!(expected.distance(vec3(0., 0., 1.)) < 1e-4);
+
+        let coasting = objects.calculate_trajectories(&physics_handler, 200, 1, None, None);
+        let draft = ManeuverNode::new(probe, 3.);
+        physics_handler.add_maneuver(draft);
+        assert!(physics_handler.update_maneuver(&draft, node));
+        assert!(!physics_handler.update_maneuver(&draft, node));
+        assert_eq!(physics_handler.get_maneuvers(), &[node]);
+        let planned = objects.calculate_trajectories(&physics_handler, 200, 1, None, None);
+        assert!(coasting[&probe].get(5) == planned[&probe].get(5));
+        assert!(
+            coasting[&probe]
+                .get(199)
+                .unwrap()
+                .distance(planned[&probe].get(199).unwrap())
+                > 1.
+        );
+
+        let rewound = objects.clone();
+        while physics_handler.get_sim_time() < 2.5 {
+            physics_handler.step(&mut objects);
+        }
+        assert_eq!(physics_handler.get_maneuvers(), &[node]);
+
+        let mut replayed = rewound;
+        physics_handler.set_clock(0., 0);
+        while physics_handler.get_sim_time() < 2.5 {
+            physics_handler.step(&mut replayed);
+        }
+        assert_eq!(replayed, objects);
+
+        let elements =
+            OrbitalElements::of(objects.get(probe).unwrap(), objects.get(sun).unwrap(), 1.)
+                .unwrap();
+        assert!(elements.apoapsis_distance().unwrap() > 60.);
+    }
+
+    #[test]
+    fn adaptive_sampling_follows_curvature() {
+        let mut objects = ObjectPool::new();
+        objects.push(Object::new(Vec3::ZERO, Vec3::ZERO, 1000., 5., WHITE));
+        let comet = objects.push(Object::particle(
+            vec3(10., 0., 0.),
+            vec3(0., 0., 150f32.sqrt()),
+            WHITE,
+        ));
+        let physics_handler = PhysicsHandler::new(1., 0.01);
+        let integrate = |sampling| {
+            TrajectoryIntegration::new(&objects, &physics_handler, 3000, 1, None, None)
+                .with_sampling(sampling)
+                .run()
+                .remove(&comet)
+                .unwrap()
+        };
+
+        let dense = integrate(None);
+        let adaptive = integrate(Some(AdaptiveSampling::new(0.05, 3000)));
+        assert!(adaptive.len() * 4 < dense.len());
+        assert_eq!(adaptive.duration(), dense.duration());
+
+        let polyline: Vec<Vec3> = adaptive.iter().copied().collect();
+        for point in dense.iter() {
+            let error = polyline
+                .windows(2)
+                .map(|segment| {
+                    let (start, end) = (segment[0], segment[1]);
+                    let t = ((*point - start).dot(end - start) / start.distance_squared(end))
+                        .clamp(0., 1.);
+                    point.distance(start.lerp(end, t))
+                })
+                .fold(f32::MAX, f32::min);
+            assert!(error <= 0.05 + 1e-3);
+        }
+
+        let mut drifting = ObjectPool::new();
+        let drifter = drifting.push(Object::particle(Vec3::ZERO, vec3(1., 0., 0.), WHITE));
+        let straight = TrajectoryIntegration::new(&drifting, &physics_handler, 3000, 1, None, None)
+            .with_sampling(Some(AdaptiveSampling::new(0.05, 3000)))
+            .run()
+            .remove(&drifter)
+            .unwrap();
+        assert!(straight.len() < 60);
+
+        let capped = integrate(Some(AdaptiveSampling::new(1e-4, 50)));
+        assert!(capped.len() <= 50);
+        assert_eq!(capped.duration(), dense.duration());
+    }
+
+    #[test]
+    fn trajectory_decimation_keeps_corners() {
+        let mut traj = Trajectory::new();
+        for i in 0..11 {
+            let y = if i == 5 { 5. } else { 0. };
+            traj.push_at(vec3(i as f32, y, 0.), i as f32);
+        }
+
+        traj.decimate();
+        assert_eq!(traj.len(), 6);
+        assert_eq!(traj.first(), Some(&Vec3::ZERO));
+        assert_eq!(traj.get(traj.len() - 1), Some(vec3(10., 0., 0.)));
+        assert!(traj.iter().any(|point| *point == vec3(5., 5., 0.)));
+        assert!(traj.times().is_sorted());
+    }
+
+    use crate::influence::*;
+
+    #[test]
+    fn spheres_of_influence_assign_primaries() {
+        let mut objects = ObjectPool::new();
+        let sun = objects.push(Object::new(Vec3::ZERO, Vec3::ZERO, 1000., 5., WHITE));
+        let planet = objects.push(Object::new(
+            vec3(100., 0., 0.),
+            vec3(0., 0., (1010f32 / 100.).sqrt()),
+            10.,
+            1.,
+            WHITE,
+        ));
+        let probe = objects.push(Object::particle(vec3(105., 0., 0.), Vec3::ZERO, WHITE));
+        let drifter = objects.push(Object::particle(vec3(50., 0., 0.), Vec3::ZERO, WHITE));
+
+        let spheres = objects.spheres_of_influence(1.);
+        assert_eq!(spheres[0].body, sun);
+        assert!(spheres[0].primary.is_none() && spheres[0].radius.is_infinite());
+        assert_eq!(spheres[1].primary, Some(sun));
+        assert_eq!(
+            (spheres[1].position, spheres[1].mass),
+            (vec3(100., 0., 0.), 10.)
+        );
+        let expected = 100. * 0.01f32.powf(0.4);
+        assert!((spheres[1].radius - expected).abs() < 0.5);
+
+        let primaries = objects.primaries(1.);
+        assert_eq!(primaries.get(&sun), None);
+        assert_eq!(primaries.get(&planet), Some(&sun));
+        assert_eq!(primaries.get(&probe), Some(&planet));
+        assert_eq!(objects.primary_of(drifter, 1.), Some(sun));
+
+        let mut tracker = InfluenceTracker::default();
+        assert!(tracker.update(&objects, 1., 0.).is_empty());
+
+        objects.get_mut(probe).unwrap().position = vec3(130., 0., 0.);
+        let events = tracker.update(&objects, 1., 2.);
+        assert_eq!(
+            events,
+            vec![SoiEvent {
+                object: probe,
+                from: Some(planet),
+                to: Some(sun),
+                time: 2.,
+            }]
+        );
+        assert_eq!(tracker.primary_of(probe), Some(sun));
+        assert_eq!(tracker.history().count(), 1);
+    }
+
+    use crate::spawn::*;
+
+    #[test]
+    fn spawn_tool_ring_is_keplerian() {
+        let center = Object::new(vec3(5., 0., 5.), vec3(1., 0., 0.), 100., 2., WHITE);
+        let batch = SpawnTool::new(SpawnShape::Ring, 32)
+            .with_radius_range(20., 20.)
+            .generate(&center, 1.);
+
+        assert_eq!(batch.len(), 32);
+        for obj in &batch {
+            let offset = obj.position - center.position;
+            let velocity = obj.velocity - center.velocity;
+            assert!((offset.length() - 20.).abs() < 1e-3);
+            assert!(obj.test_particle);
+            assert!(offset.dot(velocity).abs() < 1e-3);
+            assert!((velocity.length() - (100f32 / 20.).sqrt()).abs() < 1e-3);
+        }
+
+        let batch = SpawnTool::new(SpawnShape::Cloud, 64)
+            .with_radius_range(5., 10.)
+            .with_mass_range(0.1, 0.2)
+            .with_velocity(VelocityProfile::Static)
+            .generate(&center, 1.);
+        for obj in &batch {
+            let distance = obj.position.distance(center.position);
+            assert!((5. - 1e-3..=10. + 1e-3).contains(&distance));
+            assert!((0.1..=0.2).contains(&obj.mass));
+            assert_eq!(obj.velocity, center.velocity);
+        }
+
+        let mut objects = ObjectPool::new();
+        let mut history = EditHistory::default();
+        history.spawn_all(&mut objects, batch);
+        assert_eq!(objects.len(), 64);
+        history.undo(&mut objects);
+        assert!(objects.is_empty());
+    }
 }
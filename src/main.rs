@@ -1,6 +1,11 @@
-use gravity_sim::object::Object;
+use gravity_sim::boundary::{Boundary, BoundaryPolicy, BoundaryShape, Domain};
+use gravity_sim::diagnostics::Diagnostics;
+use gravity_sim::horizons::{self, HorizonsScale};
+use gravity_sim::recorder::Recorder;
+use gravity_sim::scenario::Scenario;
+use gravity_sim::units::{Quantity, Units};
 use gravity_sim::world::World;
-use macroquad::audio::{load_sound, play_sound, PlaySoundParams};
+use macroquad::audio::{PlaySoundParams, load_sound, play_sound};
 use macroquad::prelude::*;
 use std::env;
 
@@ -8,6 +13,11 @@ const WINDOW_TITLE: &str = "Gravity Sim";
 const WINDOW_WIDTH: i32 = 1280;
 const WINDOW_HEIGHT: i32 = 720;
 const AA_SAMPLE_COUNT: i32 = 4;
+const DOMAIN_RADIUS: f32 = 2_500.;
+const ESCAPE_DISTANCE: f32 = 1_000.;
+const HORIZONS_FALLBACK_MASS: f64 = 1e-12;
+const RECORD_INTERVAL: u64 = 5;
+const RECORD_ROWS_PER_FILE: usize = 100_000;
 
 const BG_COLOR: Color = Color {
     r: 0.06,
@@ -19,7 +29,47 @@ const BG_COLOR: Color = Color {
 #[macroquad::main(config)]
 async fn main() {
     let mut world = World::default();
-    world.objects.push(Object::default());
+    let grav_const = world.physics_handler.get_grav_const();
+    if let Some(units) = arg_value("--units").and_then(|name| Units::from_name(&name, grav_const)) {
+        world.set_units(units);
+    }
+
+    if arg_value("--units").is_some() && arg_value("--horizons").is_some() {
+        eprintln!("--horizons always uses astronomical units, ignoring --units");
+    }
+
+    let tables = arg_value("--horizons").and_then(|dir| match horizons::load_dir(&dir) {
+        Ok(tables) => Some(tables),
+        Err(err) => {
+            eprintln!("Failed to load Horizons tables from '{dir}': {err}");
+            None
+        }
+    });
+    world.objects = match tables {
+        Some(tables) => {
+            let scale = HorizonsScale::default();
+            world.set_units(scale.units);
+            horizons::import_system(&tables, &scale, HORIZONS_FALLBACK_MASS)
+        }
+        None => startup_scenario().generate(grav_const),
+    };
+    if let Some(length) = arg_value("--trail-length").and_then(|value| value.parse().ok()) {
+        world.trails.set_length(length);
+    }
+    if let Some(interval) = arg_value("--trail-interval").and_then(|value| value.parse().ok()) {
+        world.trails.set_interval(interval);
+    }
+    world.physics_handler.set_domain(Domain::new(
+        vec![Boundary::new(
+            BoundaryShape::Sphere {
+                center: Vec3::ZERO,
+                radius: DOMAIN_RADIUS,
+            },
+            BoundaryPolicy::Despawn,
+        )],
+        Some(ESCAPE_DISTANCE),
+        true,
+    ));
 
     play_music().await;
 
@@ -27,12 +77,131 @@ async fn main() {
         clear_background(BG_COLOR);
 
         world.update(get_frame_time());
+
+        if is_key_released(KeyCode::F8) {
+            world.trails.set_mode(world.trails.get_mode().next());
+        }
+
+        if is_key_released(KeyCode::F6) {
+            world.recorder = match world.recorder {
+                Some(_) => None,
+                None => Some(
+                    Recorder::new("recordings", "run", RECORD_INTERVAL, RECORD_ROWS_PER_FILE)
+                        .with_units(world.units),
+                ),
+            };
+        }
+        let sim_time = world.physics_handler.get_sim_time();
         draw_text(&get_fps().to_string(), 5., 20., 32., WHITE);
+        Diagnostics::measure(&world.objects, &world.physics_handler).draw(
+            &world.units,
+            5.,
+            44.,
+            24.,
+            WHITE,
+        );
+        draw_text(
+            &format!(
+                "t: {}",
+                world
+                    .units
+                    .format(Quantity::Time, world.physics_handler.get_sim_time())
+            ),
+            5.,
+            116.,
+            24.,
+            WHITE,
+        );
+        world.input_handler.draw_menu(5., 164., 24., WHITE);
+        world.input_handler.draw_encounters(
+            &world.units,
+            sim_time,
+            screen_width() - 360.,
+            20.,
+            24.,
+            WHITE,
+        );
+        world.input_handler.draw_orbit(
+            &world.renderer,
+            &world.units,
+            5.,
+            screen_height() - 140.,
+            24.,
+            WHITE,
+        );
+        world.input_handler.draw_lagrange(
+            &world.renderer,
+            &world.units,
+            5.,
+            screen_height() - 190.,
+            24.,
+            WHITE,
+        );
+        world.input_handler.draw_impacts(
+            &world.units,
+            sim_time,
+            screen_width() - 360.,
+            164.,
+            24.,
+            ORANGE,
+        );
+        if world.input_handler.is_showing_impacts() {
+            let impact_lines = world.input_handler.get_impacts().len().min(8) as f32;
+            world.boundary_log.draw(
+                &world.units,
+                screen_width() - 360.,
+                164. + impact_lines * 24.,
+                24.,
+                ORANGE,
+            );
+        }
+        world
+            .input_handler
+            .draw_maneuver(&world.units, sim_time, 5., 164., 24., SKYBLUE);
+        world.input_handler.draw_influence(
+            &world.influence,
+            &world.units,
+            screen_width() - 360.,
+            screen_height() - 250.,
+            24.,
+            VIOLET,
+        );
+        world
+            .timeline
+            .draw(&world.units, 5., screen_height() - 10., 24., WHITE);
 
         next_frame().await;
     }
 }
 
+fn arg_value(name: &str) -> Option<String> {
+    let args: Vec<String> = env::args().collect();
+
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
+
+fn startup_scenario() -> Scenario {
+    let Some(name) = arg_value("--scenario") else {
+        return Scenario::Default;
+    };
+
+    Scenario::from_name(&name).unwrap_or_else(|| {
+        let names: Vec<&str> = Scenario::ALL
+            .iter()
+            .map(|scenario| scenario.name())
+            .collect();
+        eprintln!(
+            "Unknown scenario '{name}', expected one of: {}. Using '{}'.",
+            names.join(", "),
+            Scenario::Default.name()
+        );
+        Scenario::Default
+    })
+}
+
 #[cfg(target_arch = "wasm32")]
 async fn play_music() {
     let st = load_sound("res/music/music.ogg")
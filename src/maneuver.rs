@@ -0,0 +1,93 @@
+use crate::object::ObjectPool;
+use crate::orbit::dominant_body;
+use macroquad::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ManeuverNode {
+    pub object: usize,
+    pub time: f32,
+    pub prograde: f32,
+    pub normal: f32,
+    pub radial: f32,
+}
+
+impl ManeuverNode {
+    pub fn new(object: usize, time: f32) -> Self {
+        ManeuverNode {
+            object,
+            time,
+            prograde: 0.,
+            normal: 0.,
+            radial: 0.,
+        }
+    }
+
+    pub fn with_delta_v(mut self, prograde: f32, normal: f32, radial: f32) -> Self {
+        self.prograde = prograde;
+        self.normal = normal;
+        self.radial = radial;
+        self
+    }
+
+    pub fn magnitude(&self) -> f32 {
+        vec3(self.prograde, self.normal, self.radial).length()
+    }
+
+    pub fn delta_v(&self, objects: &ObjectPool) -> Option<Vec3> {
+        let object = objects.get(self.object)?;
+        let (position, velocity) = match dominant_body(objects, object) {
+            Some(primary) => (
+                object.position - primary.position,
+                object.velocity - primary.velocity,
+            ),
+            None => (object.position, object.velocity),
+        };
+
+        let (prograde, normal, radial) = Self::basis(position, velocity);
+        Some(prograde * self.prograde + normal * self.normal + radial * self.radial)
+    }
+
+    pub fn apply(&self, objects: &mut ObjectPool) -> bool {
+        let Some(delta_v) = self.delta_v(objects) else {
+            return false;
+        };
+
+        match objects.get_mut(self.object) {
+            Some(object) => {
+                object.add_velocity(delta_v);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn basis(position: Vec3, velocity: Vec3) -> (Vec3, Vec3, Vec3) {
+        let prograde = velocity.try_normalize().unwrap_or(Vec3::Z);
+        let normal = position
+            .cross(velocity)
+            .try_normalize()
+            .unwrap_or_else(|| prograde.any_orthonormal_vector());
+        let radial = prograde.cross(normal);
+
+        (prograde, normal, radial)
+    }
+}
+
+pub fn execute_due(
+    maneuvers: &[ManeuverNode],
+    objects: &mut ObjectPool,
+    from: f32,
+    to: f32,
+) -> Vec<ManeuverNode> {
+    let due: Vec<ManeuverNode> = maneuvers
+        .iter()
+        .filter(|node| node.time >= from && node.time < to)
+        .copied()
+        .collect();
+
+    for node in &due {
+        node.apply(objects);
+    }
+
+    due
+}
@@ -1,7 +1,14 @@
+use crate::frame::RotatingFrame;
+use crate::influence::{self, SphereOfInfluence};
 use crate::physics::PhysicsHandler;
+use crate::prediction::TrajectoryIntegration;
 use crate::renderer::Renderer;
 use macroquad::prelude::*;
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static GENERATION: AtomicU64 = AtomicU64::new(0);
 
 #[derive(PartialEq, Debug)]
 pub struct Object {
@@ -11,6 +18,11 @@ pub struct Object {
     pub mass: f32,
     pub radius: f32,
     pub color: Color,
+    pub spin: Vec3,
+    pub orientation: Quat,
+    pub name: Option<String>,
+    pub test_particle: bool,
+    pub frozen: bool,
 }
 
 impl Object {
@@ -22,6 +34,11 @@ impl Object {
             mass,
             radius,
             color,
+            spin: Vec3::ZERO,
+            orientation: Quat::IDENTITY,
+            name: None,
+            test_particle: false,
+            frozen: false,
         }
     }
 
@@ -47,9 +64,26 @@ impl Object {
             mass: self.mass,
             radius: self.radius,
             color: self.color,
+            spin: self.spin,
+            orientation: self.orientation,
+            name: self.name.clone(),
+            test_particle: self.test_particle,
+            frozen: self.frozen,
+        }
+    }
+
+    pub fn particle(position: Vec3, velocity: Vec3, color: Color) -> Self {
+        Object {
+            test_particle: true,
+            ..Object::new(position, velocity, 0., 0.2, color)
         }
     }
 
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
     pub fn translate(&mut self, translation: Vec3) -> &mut Self {
         self.position += translation;
         self
@@ -62,11 +96,46 @@ impl Object {
 
     pub fn update_pos(&mut self, time: f32) -> &mut Self {
         self.position += self.velocity * time;
+        self.update_rotation(time);
+        self
+    }
+
+    pub fn update_rotation(&mut self, time: f32) -> &mut Self {
+        if self.spin != Vec3::ZERO {
+            self.orientation =
+                (Quat::from_scaled_axis(self.spin * time) * self.orientation).normalize();
+        }
         self
     }
 
+    pub fn moment_of_inertia(&self) -> f32 {
+        Self::sphere_inertia(self.mass, self.radius)
+    }
+
+    pub fn sphere_inertia(mass: f32, radius: f32) -> f32 {
+        0.4 * mass * radius * radius
+    }
+
+    pub fn spin_angular_momentum(&self) -> Vec3 {
+        self.moment_of_inertia() * self.spin
+    }
+
+    pub fn orbital_angular_momentum(&self) -> Vec3 {
+        self.mass * self.position.cross(self.velocity)
+    }
+
     pub fn draw(&self, renderer: &Renderer) {
-        renderer.draw_sphere(self.position, self.radius, Some(self.color));
+        if self.test_particle {
+            renderer.draw_point(self.position, self.radius, Some(self.color));
+            return;
+        }
+
+        renderer.draw_rotated_sphere(
+            self.position,
+            self.radius,
+            self.orientation,
+            Some(self.color),
+        );
         renderer.draw_arrow(self.position, self.position.with_y(0.0), Some(self.color));
     }
 
@@ -76,6 +145,8 @@ impl Object {
         physics_handler: &PhysicsHandler,
         point_count: u32,
         step: u32,
+        frame: Option<&RotatingFrame>,
+        reference: Option<usize>,
     ) -> Trajectory {
         let mut objects = objects.clone();
         let mut id = self.id;
@@ -83,28 +154,17 @@ impl Object {
             id = objects.push(self.clone());
         }
 
-        let mut trajectory = Trajectory::new();
-
-        for i in 0..point_count * step {
-            let time = physics_handler.get_timestep();
-            physics_handler.update_objects(&mut objects, time);
-
-            if i % step != 0 {
-                continue;
-            }
-
-            let obj = objects.get(id);
-
-            if obj.is_none() {
-                trajectory.end();
-                return trajectory;
-            }
-
-            let obj = obj.unwrap().clone();
-            trajectory.push(obj.position);
-        }
-
-        trajectory
+        TrajectoryIntegration::new(
+            &objects,
+            physics_handler,
+            point_count,
+            step,
+            frame.copied(),
+            reference,
+        )
+        .run()
+        .remove(&id)
+        .unwrap_or_else(|| Trajectory::new().with_reference(reference))
     }
 }
 
@@ -125,14 +185,21 @@ impl Clone for Object {
         );
 
         obj.id = self.id;
+        obj.spin = self.spin;
+        obj.orientation = self.orientation;
+        obj.name = self.name.clone();
+        obj.test_particle = self.test_particle;
+        obj.frozen = self.frozen;
         obj
     }
 }
 
-#[derive(Default)]
+#[derive(Debug)]
 pub struct ObjectPool {
     objects: Vec<Object>,
     current_id: usize,
+    groups: BTreeMap<String, Vec<usize>>,
+    generation: u64,
 }
 
 impl ObjectPool {
@@ -140,16 +207,28 @@ impl ObjectPool {
         ObjectPool {
             objects: Vec::new(),
             current_id: 0,
+            groups: BTreeMap::new(),
+            generation: GENERATION.fetch_add(1, Ordering::Relaxed),
         }
     }
 
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn mark_edited(&mut self) {
+        self.generation = GENERATION.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub fn push(&mut self, object: Object) -> usize {
+        self.mark_edited();
         self.current_id += 1;
         self.objects.push(object.clone_with_id(self.current_id));
         self.current_id
     }
 
     pub fn pop(&mut self) -> Option<Object> {
+        self.mark_edited();
         self.objects.pop()
     }
 
@@ -159,15 +238,34 @@ impl ObjectPool {
                 continue;
             }
 
+            self.mark_edited();
             self.objects.swap_remove(i);
+            for members in self.groups.values_mut() {
+                members.retain(|member| *member != id);
+            }
+            self.groups.retain(|_, members| !members.is_empty());
             return;
         }
     }
 
+    pub fn insert(&mut self, object: Object) {
+        self.mark_edited();
+        self.current_id = self.current_id.max(object.id);
+
+        match self.get_mut(object.id) {
+            Some(existing) => *existing = object,
+            None => self.objects.push(object),
+        }
+    }
+
     pub fn get(&self, id: usize) -> Option<&Object> {
         self.objects.iter().find(|obj| obj.id == id)
     }
 
+    pub fn get_mut(&mut self, id: usize) -> Option<&mut Object> {
+        self.objects.iter_mut().find(|obj| obj.id == id)
+    }
+
     pub fn get_all_in_area(&self, pos: Vec3, radius: f32) -> ObjectPool {
         ObjectPool {
             objects: self
@@ -177,9 +275,19 @@ impl ObjectPool {
                 .cloned()
                 .collect(),
             current_id: self.current_id,
+            groups: self.groups.clone(),
+            generation: GENERATION.fetch_add(1, Ordering::Relaxed),
         }
     }
 
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &Object> {
         self.objects.iter()
     }
@@ -188,53 +296,210 @@ impl ObjectPool {
         self.objects.iter_mut()
     }
 
-    pub fn calculate_trajectories(
-        &self,
-        physics_handler: &PhysicsHandler,
-        point_count: u32,
-        step: u32,
-    ) -> HashMap<usize, Trajectory> {
-        let mut ids: Vec<usize> = Vec::new();
-        let mut objects = self.clone();
-        let mut trajectories: HashMap<usize, Trajectory> = HashMap::new();
+    pub fn total_mass(&self) -> f32 {
+        self.iter().map(|obj| obj.mass).sum()
+    }
+
+    pub fn total_momentum(&self) -> Vec3 {
+        self.iter().map(|obj| obj.mass * obj.velocity).sum()
+    }
 
-        for obj in objects.iter_mut() {
-            ids.push(obj.id);
+    pub fn center_of_mass(&self) -> Option<Vec3> {
+        let total_mass = self.total_mass();
+        if total_mass == 0. {
+            return None;
         }
 
-        for i in 0..point_count * step {
-            let time = physics_handler.get_timestep();
-            physics_handler.update_objects(&mut objects, time);
+        Some(self.iter().map(|obj| obj.mass * obj.position).sum::<Vec3>() / total_mass)
+    }
 
-            if i % step != 0 {
-                continue;
+    pub fn to_barycentric_frame(&mut self) {
+        let Some(com) = self.center_of_mass() else {
+            return;
+        };
+        let com_veloc = self.total_momentum() / self.total_mass();
+
+        for obj in self.iter_mut() {
+            obj.position -= com;
+            obj.velocity -= com_veloc;
+        }
+    }
+
+    pub fn add_to_group(&mut self, name: &str, id: usize) {
+        let members = self.groups.entry(name.to_string()).or_default();
+        if !members.contains(&id) {
+            members.push(id);
+        }
+    }
+
+    pub fn remove_from_group(&mut self, name: &str, id: usize) {
+        if let Some(members) = self.groups.get_mut(name) {
+            members.retain(|member| *member != id);
+            if members.is_empty() {
+                self.groups.remove(name);
             }
+        }
+    }
 
-            for id in ids.clone().iter() {
-                if objects.iter().find(|o| o.id == *id).is_some() {
-                    continue;
-                }
+    pub fn group_names(&self) -> impl Iterator<Item = &String> {
+        self.groups.keys()
+    }
 
-                if let Some(traj) = trajectories.get_mut(id) {
-                    traj.end();
-                }
+    pub fn groups_of(&self, id: usize) -> Vec<String> {
+        self.groups
+            .iter()
+            .filter(|(_, members)| members.contains(&id))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    pub fn group_ids(&self, name: &str) -> Vec<usize> {
+        self.groups.get(name).map_or(Vec::new(), |members| {
+            members
+                .iter()
+                .copied()
+                .filter(|id| self.get(*id).is_some())
+                .collect()
+        })
+    }
 
-                ids.remove(ids.iter().position(|id| *id == *id).unwrap());
+    pub fn inherit_groups(&mut self, from: usize, to: usize) {
+        for members in self.groups.values_mut() {
+            if members.contains(&from) && !members.contains(&to) {
+                members.push(to);
             }
+        }
+    }
 
-            for obj in objects.iter_mut() {
-                if i % step != 0 {
-                    continue;
-                }
+    pub fn group_center_of_mass(&self, name: &str) -> Option<Vec3> {
+        let ids = self.group_ids(name);
+        let total_mass: f32 = ids
+            .iter()
+            .filter_map(|id| self.get(*id))
+            .map(|obj| obj.mass)
+            .sum();
+        if total_mass == 0. {
+            return None;
+        }
+
+        let weighted: Vec3 = ids
+            .iter()
+            .filter_map(|id| self.get(*id))
+            .map(|obj| obj.mass * obj.position)
+            .sum();
+
+        Some(weighted / total_mass)
+    }
+
+    pub fn translate_group(&mut self, name: &str, translation: Vec3) {
+        self.mark_edited();
+        for id in self.group_ids(name) {
+            if let Some(obj) = self.get_mut(id) {
+                obj.translate(translation);
+            }
+        }
+    }
+
+    pub fn boost_group(&mut self, name: &str, velocity: Vec3) {
+        self.mark_edited();
+        for id in self.group_ids(name) {
+            if let Some(obj) = self.get_mut(id) {
+                obj.add_velocity(velocity);
+            }
+        }
+    }
 
-                trajectories
-                    .entry(obj.id)
-                    .or_insert(Trajectory::new())
-                    .push(obj.position);
+    pub fn rotate_group(&mut self, name: &str, rotation: Quat) {
+        let Some(pivot) = self.group_center_of_mass(name) else {
+            return;
+        };
+        self.mark_edited();
+
+        for id in self.group_ids(name) {
+            if let Some(obj) = self.get_mut(id) {
+                obj.position = pivot + rotation * (obj.position - pivot);
+                obj.velocity = rotation * obj.velocity;
+                obj.orientation = (rotation * obj.orientation).normalize();
+            }
+        }
+    }
+
+    pub fn scale_group(&mut self, name: &str, factor: f32) {
+        let Some(pivot) = self.group_center_of_mass(name) else {
+            return;
+        };
+        self.mark_edited();
+
+        for id in self.group_ids(name) {
+            if let Some(obj) = self.get_mut(id) {
+                obj.position = pivot + (obj.position - pivot) * factor;
             }
         }
+    }
+
+    pub fn duplicate_group(&mut self, name: &str, new_name: &str, offset: Vec3) -> Vec<usize> {
+        let mut new_ids = Vec::new();
+
+        for id in self.group_ids(name) {
+            let Some(obj) = self.get(id) else {
+                continue;
+            };
+
+            let mut copy = obj.clone();
+            copy.translate(offset);
+
+            let new_id = self.push(copy);
+            self.add_to_group(new_name, new_id);
+            new_ids.push(new_id);
+        }
+
+        new_ids
+    }
+
+    pub fn delete_group(&mut self, name: &str) -> Vec<Object> {
+        let removed: Vec<Object> = self
+            .group_ids(name)
+            .iter()
+            .filter_map(|id| self.get(*id).cloned())
+            .collect();
+
+        for obj in &removed {
+            self.remove(obj.id);
+        }
+        self.groups.remove(name);
+
+        removed
+    }
+
+    pub fn calculate_trajectories(
+        &self,
+        physics_handler: &PhysicsHandler,
+        point_count: u32,
+        step: u32,
+        frame: Option<&RotatingFrame>,
+        reference: Option<usize>,
+    ) -> HashMap<usize, Trajectory> {
+        TrajectoryIntegration::new(
+            self,
+            physics_handler,
+            point_count,
+            step,
+            frame.copied(),
+            reference,
+        )
+        .run()
+    }
+
+    pub fn spheres_of_influence(&self, grav_const: f32) -> Vec<SphereOfInfluence> {
+        influence::spheres_of_influence(self, grav_const)
+    }
+
+    pub fn primaries(&self, grav_const: f32) -> HashMap<usize, usize> {
+        influence::assign_primaries(self, &self.spheres_of_influence(grav_const))
+    }
 
-        trajectories
+    pub fn primary_of(&self, id: usize, grav_const: f32) -> Option<usize> {
+        self.primaries(grav_const).get(&id).copied()
     }
 
     pub fn draw_all(&self, renderer: &Renderer) {
@@ -244,6 +509,20 @@ impl ObjectPool {
     }
 }
 
+impl Default for ObjectPool {
+    fn default() -> Self {
+        ObjectPool::new()
+    }
+}
+
+impl PartialEq for ObjectPool {
+    fn eq(&self, other: &Self) -> bool {
+        self.objects == other.objects
+            && self.current_id == other.current_id
+            && self.groups == other.groups
+    }
+}
+
 impl Clone for ObjectPool {
     fn clone(&self) -> Self {
         let mut vec = Vec::default();
@@ -254,31 +533,126 @@ impl Clone for ObjectPool {
         ObjectPool {
             objects: vec,
             current_id: self.current_id,
+            groups: self.groups.clone(),
+            generation: self.generation,
         }
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Trajectory {
     points: Vec<Vec3>,
-    has_end: bool,
+    times: Vec<f32>,
+    termination: Termination,
+    reference: Option<usize>,
 }
 
 impl Trajectory {
     pub fn new() -> Self {
         Trajectory {
             points: Vec::new(),
-            has_end: false,
+            times: Vec::new(),
+            termination: Termination::Horizon,
+            reference: None,
         }
     }
 
-    pub fn push(&mut self, point: Vec3) -> &mut Trajectory {
+    pub fn with_reference(mut self, reference: Option<usize>) -> Self {
+        self.reference = reference;
+        self
+    }
+
+    pub fn reference(&self) -> Option<usize> {
+        self.reference
+    }
+
+    pub fn push_at(&mut self, point: Vec3, time: f32) -> &mut Trajectory {
         self.points.push(point);
+        self.times.push(time);
         self
     }
 
-    pub fn end(&mut self) -> &mut Trajectory {
-        self.has_end = true;
+    pub fn replace_last(&mut self, point: Vec3, time: f32) -> &mut Trajectory {
+        match (self.points.last_mut(), self.times.last_mut()) {
+            (Some(last), Some(last_time)) => {
+                *last = point;
+                *last_time = time;
+                self
+            }
+            _ => self.push_at(point, time),
+        }
+    }
+
+    pub fn decimate(&mut self) -> &mut Trajectory {
+        let len = self.points.len();
+        if len <= 2 {
+            return self;
+        }
+
+        let points = &self.points;
+        let area = |a: usize, b: usize, c: usize| {
+            (points[b] - points[a])
+                .cross(points[c] - points[a])
+                .length()
+        };
+        let mut prev: Vec<usize> = (0..len).map(|i| i.saturating_sub(1)).collect();
+        let mut next: Vec<usize> = (1..=len).collect();
+        let mut areas: Vec<f32> = (0..len)
+            .map(|i| match i {
+                0 => f32::INFINITY,
+                _ if i == len - 1 => f32::INFINITY,
+                _ => area(i - 1, i, i + 1),
+            })
+            .collect();
+
+        let mut heap: BinaryHeap<Reverse<(u32, usize)>> = (1..len - 1)
+            .map(|i| Reverse((areas[i].to_bits(), i)))
+            .collect();
+        let mut removed = vec![false; len];
+        let mut remaining = len;
+        while remaining > len.div_ceil(2) {
+            let Some(Reverse((bits, i))) = heap.pop() else {
+                break;
+            };
+            if removed[i] || bits != areas[i].to_bits() {
+                continue;
+            }
+
+            removed[i] = true;
+            remaining -= 1;
+            let (before, after) = (prev[i], next[i]);
+            next[before] = after;
+            prev[after] = before;
+            for j in [before, after] {
+                if j != 0 && j != len - 1 {
+                    areas[j] = area(prev[j], j, next[j]);
+                    heap.push(Reverse((areas[j].to_bits(), j)));
+                }
+            }
+        }
+
+        let mut i = 0;
+        self.points.retain(|_| {
+            i += 1;
+            !removed[i - 1]
+        });
+        let mut i = 0;
+        self.times.retain(|_| {
+            i += 1;
+            !removed[i - 1]
+        });
+        self
+    }
+
+    pub fn drop_before(&mut self, time: f32) -> &mut Trajectory {
+        let count = self.times.partition_point(|t| *t < time).saturating_sub(1);
+        self.points.drain(..count);
+        self.times.drain(..count);
+        self
+    }
+
+    pub fn end(&mut self, termination: Termination) -> &mut Trajectory {
+        self.termination = termination;
         self
     }
 
@@ -286,6 +660,10 @@ impl Trajectory {
         self.points.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
     pub fn first(&self) -> Option<&Vec3> {
         self.points.first()
     }
@@ -294,21 +672,99 @@ impl Trajectory {
         self.points.iter()
     }
 
+    pub fn get(&self, index: usize) -> Option<Vec3> {
+        self.points.get(index).copied()
+    }
+
+    pub fn time(&self, index: usize) -> Option<f32> {
+        self.times.get(index).copied()
+    }
+
+    pub fn times(&self) -> impl Iterator<Item = &f32> {
+        self.times.iter()
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.times.last().copied().unwrap_or(0.)
+    }
+
+    pub fn has_end(&self) -> bool {
+        self.termination != Termination::Horizon
+    }
+
+    pub fn termination(&self) -> Termination {
+        self.termination
+    }
+
+    pub fn sample_at(&self, time: f32) -> Option<Vec3> {
+        let first = *self.times.first()?;
+        let last = *self.times.last()?;
+        if time < first || time > last {
+            return None;
+        }
+
+        let i = self.times.partition_point(|t| *t < time);
+        if i == 0 {
+            return self.points.first().copied();
+        }
+
+        let (t0, t1) = (self.times[i - 1], self.times[i]);
+        let factor = if t1 > t0 { (time - t0) / (t1 - t0) } else { 1. };
+        Some(self.points[i - 1].lerp(self.points[i], factor))
+    }
+
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Vec3> {
         self.points.iter_mut()
     }
 
     pub fn draw(&self, renderer: &Renderer, color: Option<Color>, end_sphere_radius: f32) {
+        self.draw_from(renderer, Vec3::ZERO, color, end_sphere_radius);
+    }
+
+    pub fn draw_from(
+        &self,
+        renderer: &Renderer,
+        origin: Vec3,
+        color: Option<Color>,
+        end_sphere_radius: f32,
+    ) {
         for (i, point) in self.points.iter().enumerate() {
             if i == 0 {
                 continue;
             }
 
-            renderer.draw_line(self.points[i - 1], *point, color);
+            renderer.draw_line(origin + self.points[i - 1], origin + *point, color);
 
-            if i == self.points.len() - 1 && self.has_end {
-                renderer.draw_halo(*point, end_sphere_radius, Some(RED));
+            if i == self.points.len() - 1 {
+                let end_color = match self.termination {
+                    Termination::Horizon => continue,
+                    Termination::Collision { .. } => RED,
+                    Termination::LeftDomain { .. } => YELLOW,
+                };
+                renderer.draw_halo(origin + *point, end_sphere_radius, Some(end_color));
             }
         }
     }
 }
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Termination {
+    #[default]
+    Horizon,
+    Collision {
+        other: usize,
+        time: f32,
+    },
+    LeftDomain {
+        time: f32,
+    },
+}
+
+impl Termination {
+    pub fn time(&self) -> Option<f32> {
+        match self {
+            Termination::Horizon => None,
+            Termination::Collision { time, .. } | Termination::LeftDomain { time } => Some(*time),
+        }
+    }
+}
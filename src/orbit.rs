@@ -0,0 +1,187 @@
+use crate::object::{Object, ObjectPool};
+use macroquad::prelude::*;
+use std::f32::consts::TAU;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrbitalElements {
+    pub primary: usize,
+    pub semi_major_axis: f32,
+    pub eccentricity: f32,
+    pub period: Option<f32>,
+    pub periapsis: Vec3,
+    pub apoapsis: Option<Vec3>,
+}
+
+impl OrbitalElements {
+    pub fn from_state(primary: usize, position: Vec3, velocity: Vec3, mu: f32) -> Option<Self> {
+        let distance = position.length();
+        if distance == 0. || mu <= 0. {
+            return None;
+        }
+
+        let energy = velocity.length_squared() / 2. - mu / distance;
+        if energy == 0. {
+            return None;
+        }
+
+        let angular_momentum = position.cross(velocity);
+        let eccentricity_vec = velocity.cross(angular_momentum) / mu - position / distance;
+        let eccentricity = eccentricity_vec.length();
+        let semi_major_axis = -mu / (2. * energy);
+
+        let periapsis_dir = eccentricity_vec
+            .try_normalize()
+            .unwrap_or(position / distance);
+        let periapsis = periapsis_dir * semi_major_axis * (1. - eccentricity);
+
+        let (period, apoapsis) = if eccentricity < 1. {
+            (
+                Some(TAU * (semi_major_axis.powi(3) / mu).sqrt()),
+                Some(-periapsis_dir * semi_major_axis * (1. + eccentricity)),
+            )
+        } else {
+            (None, None)
+        };
+
+        Some(OrbitalElements {
+            primary,
+            semi_major_axis,
+            eccentricity,
+            period,
+            periapsis,
+            apoapsis,
+        })
+    }
+
+    pub fn of(object: &Object, primary: &Object, grav_const: f32) -> Option<Self> {
+        Self::from_state(
+            primary.id,
+            object.position - primary.position,
+            object.velocity - primary.velocity,
+            grav_const * (primary.mass + object.mass),
+        )
+    }
+
+    pub fn is_bound(&self) -> bool {
+        self.eccentricity < 1.
+    }
+
+    pub fn periapsis_distance(&self) -> f32 {
+        self.periapsis.length()
+    }
+
+    pub fn apoapsis_distance(&self) -> Option<f32> {
+        self.apoapsis.map(|apoapsis| apoapsis.length())
+    }
+}
+
+pub fn dominant_body<'a>(objects: &'a ObjectPool, object: &Object) -> Option<&'a Object> {
+    objects
+        .iter()
+        .filter(|other| !other.test_particle && other.mass > object.mass)
+        .filter(|other| other.position != object.position)
+        .max_by(|a, b| {
+            let pull =
+                |other: &Object| other.mass / other.position.distance_squared(object.position);
+            pull(a).total_cmp(&pull(b))
+        })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LagrangeSystem {
+    pub primary: usize,
+    pub secondary: usize,
+    pub points: [Vec3; 5],
+    pub hill_radius: f32,
+    pub primary_hill_radius: Option<f32>,
+}
+
+impl LagrangeSystem {
+    pub fn new(objects: &ObjectPool, first: usize, second: usize, grav_const: f32) -> Option<Self> {
+        let (first, second) = (objects.get(first)?, objects.get(second)?);
+        let (primary, secondary) = if first.mass >= second.mass {
+            (first, second)
+        } else {
+            (second, first)
+        };
+
+        let total_mass = primary.mass + secondary.mass;
+        let offset = secondary.position - primary.position;
+        let separation = offset.length();
+        if total_mass <= 0. || separation == 0. {
+            return None;
+        }
+
+        let mu = secondary.mass / total_mass;
+        let x_axis = offset / separation;
+        let normal = offset
+            .cross(secondary.velocity - primary.velocity)
+            .try_normalize()
+            .filter(|normal| normal.dot(x_axis).abs() < 0.99)
+            .unwrap_or(Vec3::Y);
+        let y_axis = normal.cross(x_axis).try_normalize()?;
+        let barycenter =
+            (primary.position * primary.mass + secondary.position * secondary.mass) / total_mass;
+        let to_world = |x: f32, y: f32| barycenter + (x_axis * x + y_axis * y) * separation;
+
+        let gradient = |x: f32| {
+            let r1 = x + mu;
+            let r2 = x - 1. + mu;
+            x - (1. - mu) * r1 / r1.abs().powi(3) - mu * r2 / r2.abs().powi(3)
+        };
+        let l1 = bisect(gradient, -mu + 1e-6, 1. - mu - 1e-6);
+        let l2 = bisect(gradient, 1. - mu + 1e-6, 2.);
+        let l3 = bisect(gradient, -2., -mu - 1e-6);
+        let height = 3f32.sqrt() / 2.;
+
+        Some(LagrangeSystem {
+            primary: primary.id,
+            secondary: secondary.id,
+            points: [
+                to_world(l1, 0.),
+                to_world(l2, 0.),
+                to_world(l3, 0.),
+                to_world(0.5 - mu, height),
+                to_world(0.5 - mu, -height),
+            ],
+            hill_radius: hill_radius(secondary, primary, grav_const),
+            primary_hill_radius: dominant_body(objects, primary)
+                .map(|dominant| hill_radius(primary, dominant, grav_const)),
+        })
+    }
+}
+
+pub fn hill_radius(body: &Object, primary: &Object, grav_const: f32) -> f32 {
+    let distance = OrbitalElements::of(body, primary, grav_const)
+        .filter(|elements| elements.is_bound())
+        .map_or(body.position.distance(primary.position), |elements| {
+            elements.periapsis_distance()
+        });
+
+    distance * (body.mass / (3. * primary.mass)).cbrt()
+}
+
+pub fn laplace_radius(body: &Object, primary: &Object, grav_const: f32) -> f32 {
+    let distance = OrbitalElements::of(body, primary, grav_const)
+        .filter(|elements| elements.is_bound())
+        .map_or(body.position.distance(primary.position), |elements| {
+            elements.semi_major_axis
+        });
+
+    distance * (body.mass / primary.mass).powf(0.4)
+}
+
+fn bisect(f: impl Fn(f32) -> f32, mut low: f32, mut high: f32) -> f32 {
+    let rising = f(low) < f(high);
+
+    for _ in 0..64 {
+        let mid = (low + high) / 2.;
+        if (f(mid) < 0.) == rising {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    (low + high) / 2.
+}
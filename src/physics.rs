@@ -1,11 +1,26 @@
+use crate::boundary::{BoundaryEvent, Domain};
+use crate::maneuver::{self, ManeuverNode};
 use crate::object::{Object, ObjectPool};
 use macroquad::prelude::*;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Collision {
+    pub object: usize,
+    pub other: usize,
+}
+
 #[derive(Clone)]
 pub struct PhysicsHandler {
     grav_const: f32,
     timestep: f32,
     accumulator: f32,
+    sim_time: f32,
+    step_count: u64,
+    domain: Domain,
+    auto_recenter: bool,
+    frame_rotation: Option<Vec3>,
+    events: Vec<BoundaryEvent>,
+    maneuvers: Vec<ManeuverNode>,
 }
 
 impl PhysicsHandler {
@@ -14,83 +29,172 @@ impl PhysicsHandler {
             grav_const,
             timestep,
             accumulator: 0.0,
+            sim_time: 0.0,
+            step_count: 0,
+            domain: Domain::default(),
+            auto_recenter: false,
+            frame_rotation: None,
+            events: Vec::new(),
+            maneuvers: Vec::new(),
         }
     }
 
     pub fn update(&mut self, objects: &mut ObjectPool, dt: f32) {
+        self.update_with(objects, dt, |_, _| {});
+    }
+
+    pub fn update_with<F>(&mut self, objects: &mut ObjectPool, dt: f32, mut on_step: F)
+    where
+        F: FnMut(&PhysicsHandler, &ObjectPool),
+    {
         self.accumulator += dt;
         while self.accumulator > self.timestep {
-            self.update_objects(objects, self.timestep);
+            self.step(objects);
+            on_step(self, objects);
             self.accumulator -= self.timestep;
         }
     }
 
-    pub fn update_objects(&self, objects: &mut ObjectPool, time: f32) {
-        let mut clone = objects.clone();
-
-        for obj in clone.iter_mut() {
-            obj.add_velocity(self.get_obj_veloc(obj, objects, time));
+    pub fn step(&mut self, objects: &mut ObjectPool) -> Vec<Collision> {
+        self.execute_maneuvers(objects, self.sim_time, self.sim_time + self.timestep);
+        let collisions = self.update_objects(objects, self.timestep);
+        self.events
+            .extend(self.domain.apply(objects, self.grav_const));
+        if self.auto_recenter {
+            objects.to_barycentric_frame();
         }
 
-        for obj in clone.iter_mut() {
-            obj.update_pos(time);
-        }
+        self.sim_time += self.timestep;
+        self.step_count += 1;
+
+        collisions
+    }
 
-        for obj in objects.iter_mut() {
-            self.handle_collisions(&mut clone, obj);
+    pub fn update_objects(&self, objects: &mut ObjectPool, time: f32) -> Vec<Collision> {
+        let sources: Vec<Object> = objects
+            .iter()
+            .filter(|obj| !obj.test_particle)
+            .cloned()
+            .collect();
+        let massive: Vec<&Object> = sources.iter().collect();
+
+        for obj in objects.iter_mut().filter(|obj| !obj.frozen) {
+            obj.add_velocity(self.get_veloc_from(obj, &massive, time));
+            obj.update_pos(time);
         }
 
-        *objects = clone;
+        self.handle_collisions(objects)
     }
 
-    pub fn handle_collisions(&self, objects: &mut ObjectPool, object: &mut Object) {
-        for other in objects
-            .get_all_in_area(object.position, object.radius)
-            .iter_mut()
-        {
-            if object.id == other.id {
-                continue;
-            }
-
-            let object_momentum = object.mass * object.velocity;
-            let other_momentum = other.mass * other.velocity;
-            let combined_mass = object.mass + other.mass;
-            let combined_radius = object.radius + other.radius;
+    pub fn handle_collisions(&self, objects: &mut ObjectPool) -> Vec<Collision> {
+        let mut collisions = Vec::new();
+        let mut bodies: Vec<Object> = objects
+            .iter()
+            .filter(|obj| !obj.test_particle)
+            .cloned()
+            .collect();
 
-            let new_veloc = (object_momentum + other_momentum) / combined_mass;
-            let largest: &Object = if object.mass > other.mass {
-                object
-            } else {
-                other
+        let mut i = 0;
+        while i < bodies.len() {
+            let Some(j) =
+                (0..bodies.len()).find(|j| *j != i && Self::is_touching(&bodies[i], &bodies[*j]))
+            else {
+                i += 1;
+                continue;
             };
 
-            let smallest: &Object = if object.mass <= other.mass {
-                object
+            let other = bodies.remove(j);
+            if j < i {
+                i -= 1;
+            }
+
+            let merged = Self::merge(&bodies[i], &other);
+            let largest = if bodies[i].mass > other.mass {
+                bodies[i].id
             } else {
-                other
+                other.id
             };
+            let merged_id = objects.push(merged);
+            objects.inherit_groups(largest, merged_id);
+            objects.remove(bodies[i].id);
+            objects.remove(other.id);
+            collisions.push(Collision {
+                object: bodies[i].id,
+                other: other.id,
+            });
+            collisions.push(Collision {
+                object: other.id,
+                other: bodies[i].id,
+            });
 
-            objects.push(Object::new(
-                largest.position,
-                new_veloc,
-                combined_mass,
-                combined_radius,
-                Self::mix_color(largest.color, smallest.color, smallest.mass / largest.mass),
-            ));
+            if let Some(merged) = objects.get(merged_id) {
+                bodies[i] = merged.clone();
+            }
+        }
 
-            objects.remove(object.id);
-            objects.remove(other.id);
+        let absorbed: Vec<Collision> = objects
+            .iter()
+            .filter(|obj| obj.test_particle)
+            .filter_map(|obj| {
+                let body = bodies.iter().find(|body| Self::is_touching(obj, body))?;
+                Some(Collision {
+                    object: obj.id,
+                    other: body.id,
+                })
+            })
+            .collect();
+        for collision in &absorbed {
+            objects.remove(collision.object);
         }
+        collisions.extend(absorbed);
+
+        collisions
+    }
+
+    fn is_touching(a: &Object, b: &Object) -> bool {
+        (a.position - b.position).length() <= a.radius + b.radius
+    }
+
+    fn merge(object: &Object, other: &Object) -> Object {
+        let object_momentum = object.mass * object.velocity;
+        let other_momentum = other.mass * other.velocity;
+        let combined_mass = object.mass + other.mass;
+        let combined_radius = object.radius + other.radius;
+
+        let new_veloc = (object_momentum + other_momentum) / combined_mass;
+        let new_pos = (object.position * object.mass + other.position * other.mass) / combined_mass;
+        let (largest, smallest) = if object.mass > other.mass {
+            (object, other)
+        } else {
+            (other, object)
+        };
+
+        let mut merged = Object::new(
+            new_pos,
+            new_veloc,
+            combined_mass,
+            combined_radius,
+            Self::mix_color(largest.color, smallest.color, smallest.mass / largest.mass),
+        );
+        merged.spin = Self::merged_spin(object, other, combined_radius);
+        merged.orientation = largest.orientation;
+        merged.name = largest.name.clone();
+        merged
     }
 
     pub fn get_obj_veloc(&self, object: &Object, objects: &ObjectPool, time: f32) -> Vec3 {
+        let massive: Vec<&Object> = objects.iter().filter(|obj| !obj.test_particle).collect();
+        self.get_veloc_from(object, &massive, time)
+    }
+
+    pub fn get_veloc_from(&self, object: &Object, massive: &[&Object], time: f32) -> Vec3 {
         let mut veloc = Vec3::ZERO;
 
-        for other in objects.iter() {
+        for other in massive {
             veloc += self.get_grav_veloc(other.mass, other.position - object.position, time);
         }
 
-        veloc
+        veloc + self.get_fictitious_veloc(object, time)
     }
 
     pub fn get_grav_veloc(&self, m2: f32, dist: Vec3, time: f32) -> Vec3 {
@@ -133,6 +237,140 @@ impl PhysicsHandler {
         self.timestep
     }
 
+    pub fn get_sim_time(&self) -> f32 {
+        self.sim_time
+    }
+
+    pub fn get_step_count(&self) -> u64 {
+        self.step_count
+    }
+
+    pub fn set_clock(&mut self, sim_time: f32, step_count: u64) {
+        self.sim_time = sim_time;
+        self.step_count = step_count;
+        self.accumulator = 0.;
+    }
+
+    pub fn set_timestep(&mut self, timestep: f32) {
+        self.timestep = timestep;
+    }
+
+    pub fn get_grav_const(&self) -> f32 {
+        self.grav_const
+    }
+
+    pub fn set_grav_const(&mut self, grav_const: f32) {
+        self.grav_const = grav_const;
+    }
+
+    pub fn get_domain(&self) -> &Domain {
+        &self.domain
+    }
+
+    pub fn set_domain(&mut self, domain: Domain) {
+        self.domain = domain;
+    }
+
+    pub fn get_auto_recenter(&self) -> bool {
+        self.auto_recenter
+    }
+
+    pub fn set_auto_recenter(&mut self, auto_recenter: bool) {
+        self.auto_recenter = auto_recenter;
+    }
+
+    pub fn get_frame_rotation(&self) -> Option<Vec3> {
+        self.frame_rotation
+    }
+
+    pub fn set_frame_rotation(&mut self, frame_rotation: Option<Vec3>) {
+        self.frame_rotation = frame_rotation;
+    }
+
+    pub fn get_fictitious_veloc(&self, object: &Object, time: f32) -> Vec3 {
+        let Some(omega) = self.frame_rotation else {
+            return Vec3::ZERO;
+        };
+
+        let coriolis = -2. * omega.cross(object.velocity);
+        let centrifugal = -omega.cross(omega.cross(object.position));
+        (coriolis + centrifugal) * time
+    }
+
+    pub fn execute_maneuvers(
+        &self,
+        objects: &mut ObjectPool,
+        from: f32,
+        to: f32,
+    ) -> Vec<ManeuverNode> {
+        if self.maneuvers.is_empty() {
+            return Vec::new();
+        }
+
+        maneuver::execute_due(&self.maneuvers, objects, from, to)
+    }
+
+    pub fn add_maneuver(&mut self, mut node: ManeuverNode) {
+        node.time = node.time.max(self.sim_time);
+        self.maneuvers.push(node);
+        self.maneuvers.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+
+    pub fn remove_maneuver(&mut self, node: &ManeuverNode) -> bool {
+        match self.maneuvers.iter().position(|planned| planned == node) {
+            Some(index) => {
+                self.maneuvers.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn update_maneuver(&mut self, node: &ManeuverNode, mut updated: ManeuverNode) -> bool {
+        updated.time = updated.time.max(self.sim_time);
+        let Some(planned) = self.maneuvers.iter_mut().find(|planned| *planned == node) else {
+            return false;
+        };
+
+        *planned = updated;
+        self.maneuvers.sort_by(|a, b| a.time.total_cmp(&b.time));
+        true
+    }
+
+    pub fn remove_maneuvers(&mut self, object: usize) -> Vec<ManeuverNode> {
+        let (removed, kept) = self
+            .maneuvers
+            .iter()
+            .partition(|node| node.object == object);
+        self.maneuvers = kept;
+        removed
+    }
+
+    pub fn get_maneuvers(&self) -> &[ManeuverNode] {
+        &self.maneuvers
+    }
+
+    pub fn clear_maneuvers(&mut self) {
+        self.maneuvers.clear();
+    }
+
+    pub fn drain_events(&mut self) -> Vec<BoundaryEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn merged_spin(a: &Object, b: &Object, radius: f32) -> Vec3 {
+        let combined_mass = a.mass + b.mass;
+        let inertia = Object::sphere_inertia(combined_mass, radius);
+        if inertia == 0. {
+            return Vec3::ZERO;
+        }
+
+        let reduced_mass = a.mass * b.mass / combined_mass;
+        let orbital = reduced_mass * (a.position - b.position).cross(a.velocity - b.velocity);
+
+        (orbital + a.spin_angular_momentum() + b.spin_angular_momentum()) / inertia
+    }
+
     fn mix_color(c1: Color, c2: Color, factor: f32) -> Color {
         Color {
             r: c1.r * (1. - factor) + c2.r * factor,
@@ -145,10 +383,6 @@ impl PhysicsHandler {
 
 impl Default for PhysicsHandler {
     fn default() -> PhysicsHandler {
-        PhysicsHandler {
-            grav_const: 1.0,
-            timestep: 0.2,
-            accumulator: 0.,
-        }
+        PhysicsHandler::new(1.0, 0.2)
     }
 }
@@ -0,0 +1,551 @@
+use crate::boundary::BoundaryEvent;
+use crate::frame::RotatingFrame;
+use crate::maneuver::ManeuverNode;
+use crate::object::{ObjectPool, Termination, Trajectory};
+use crate::physics::PhysicsHandler;
+use macroquad::prelude::*;
+use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::mpsc::{self, Receiver, Sender};
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+use std::time::Duration;
+
+const STEPS_PER_CHECK: u32 = 64;
+const MAX_EXTENSION: u64 = 256;
+const MAX_SKIPPED: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AdaptiveSampling {
+    pub tolerance: f32,
+    pub max_points: usize,
+}
+
+impl AdaptiveSampling {
+    pub fn new(tolerance: f32, max_points: usize) -> Self {
+        AdaptiveSampling {
+            tolerance,
+            max_points: max_points.max(2),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Simplifier {
+    skipped: Vec<Vec3>,
+    tolerance: f32,
+}
+
+impl Simplifier {
+    fn new(tolerance: f32) -> Self {
+        Simplifier {
+            skipped: Vec::new(),
+            tolerance,
+        }
+    }
+
+    fn push(&mut self, traj: &mut Trajectory, point: Vec3, time: f32, max_points: usize) {
+        let len = traj.len();
+        if len < 2 {
+            traj.push_at(point, time);
+            return;
+        }
+
+        let (Some(anchor), Some(head)) = (traj.get(len - 2), traj.get(len - 1)) else {
+            return;
+        };
+
+        let fits = self.skipped.len() < MAX_SKIPPED
+            && self
+                .skipped
+                .iter()
+                .chain([&head])
+                .all(|skipped| distance_to_segment(*skipped, anchor, point) <= self.tolerance);
+
+        if fits {
+            self.skipped.push(head);
+            traj.replace_last(point, time);
+        } else {
+            self.skipped.clear();
+            traj.push_at(point, time);
+        }
+
+        if traj.len() > max_points {
+            traj.decimate();
+            self.skipped.clear();
+            self.tolerance *= 2.;
+        }
+    }
+}
+
+fn distance_to_segment(point: Vec3, start: Vec3, end: Vec3) -> f32 {
+    let segment = end - start;
+    let length_squared = segment.length_squared();
+    if length_squared == 0. {
+        return point.distance(start);
+    }
+
+    let t = ((point - start).dot(segment) / length_squared).clamp(0., 1.);
+    point.distance(start + segment * t)
+}
+
+pub struct TrajectoryIntegration {
+    objects: ObjectPool,
+    physics_handler: PhysicsHandler,
+    frame: Option<RotatingFrame>,
+    reference: Option<usize>,
+    origin: Vec3,
+    ids: Vec<usize>,
+    trajectories: HashMap<usize, Trajectory>,
+    sampling: Option<AdaptiveSampling>,
+    simplifiers: HashMap<usize, Simplifier>,
+    step: u32,
+    current_step: u32,
+    total_steps: u32,
+}
+
+impl TrajectoryIntegration {
+    pub fn new(
+        objects: &ObjectPool,
+        physics_handler: &PhysicsHandler,
+        point_count: u32,
+        step: u32,
+        frame: Option<RotatingFrame>,
+        reference: Option<usize>,
+    ) -> Self {
+        TrajectoryIntegration {
+            objects: objects.clone(),
+            physics_handler: physics_handler.clone(),
+            frame,
+            reference,
+            origin: Vec3::ZERO,
+            ids: objects.iter().map(|obj| obj.id).collect(),
+            trajectories: HashMap::new(),
+            sampling: None,
+            simplifiers: HashMap::new(),
+            step: step.max(1),
+            current_step: 0,
+            total_steps: point_count * step.max(1),
+        }
+    }
+
+    pub fn with_sampling(mut self, sampling: Option<AdaptiveSampling>) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    pub fn advance(&mut self, steps: u32) -> bool {
+        let end = (self.current_step + steps).min(self.total_steps);
+
+        while self.current_step < end {
+            let i = self.current_step;
+            self.current_step += 1;
+
+            let collisions = self.physics_handler.step(&mut self.objects);
+            let elapsed = self.physics_handler.get_sim_time();
+            let left_domain: Vec<usize> = self
+                .physics_handler
+                .drain_events()
+                .iter()
+                .filter(|event| {
+                    matches!(
+                        event,
+                        BoundaryEvent::Despawned(_) | BoundaryEvent::Escaped(_)
+                    )
+                })
+                .map(BoundaryEvent::id)
+                .collect();
+
+            let objects = &self.objects;
+            let trajectories = &mut self.trajectories;
+            let reference = self.reference;
+            self.ids.retain(|id| {
+                if objects.get(*id).is_some() {
+                    return true;
+                }
+
+                let termination = if left_domain.contains(id) {
+                    Termination::LeftDomain { time: elapsed }
+                } else {
+                    Termination::Collision {
+                        other: collisions
+                            .iter()
+                            .find(|collision| collision.object == *id)
+                            .map_or(*id, |collision| collision.other),
+                        time: elapsed,
+                    }
+                };
+                trajectories
+                    .entry(*id)
+                    .or_insert_with(|| Trajectory::new().with_reference(reference))
+                    .end(termination);
+                false
+            });
+
+            if self.sampling.is_none() && !i.is_multiple_of(self.step) {
+                continue;
+            }
+
+            let transform = self.frame.and_then(|frame| frame.transform(&self.objects));
+            let to_frame =
+                |point: Vec3| transform.map_or(point, |transform| transform.point_to_frame(point));
+
+            if let Some(reference) = self.reference.and_then(|id| self.objects.get(id)) {
+                self.origin = to_frame(reference.position);
+            }
+
+            for obj in self.objects.iter() {
+                let reference = self.reference;
+                let point = to_frame(obj.position) - self.origin;
+                let traj = self
+                    .trajectories
+                    .entry(obj.id)
+                    .or_insert_with(|| Trajectory::new().with_reference(reference));
+
+                match self.sampling {
+                    Some(sampling) => self
+                        .simplifiers
+                        .entry(obj.id)
+                        .or_insert_with(|| Simplifier::new(sampling.tolerance))
+                        .push(traj, point, elapsed, sampling.max_points),
+                    None => {
+                        traj.push_at(point, elapsed);
+                    }
+                }
+            }
+        }
+
+        self.is_complete()
+    }
+
+    pub fn advance_for(&mut self, budget: Duration) -> bool {
+        let start = now();
+
+        while !self.advance(STEPS_PER_CHECK) {
+            if now() - start >= budget.as_secs_f64() {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub fn run(mut self) -> HashMap<usize, Trajectory> {
+        self.advance(self.total_steps);
+        self.trajectories
+    }
+
+    pub fn extend(&mut self, steps: u32, start_time: f32) -> bool {
+        self.total_steps += steps;
+        let complete = self.advance(steps);
+
+        for traj in self.trajectories.values_mut() {
+            traj.drop_before(start_time);
+        }
+        self.trajectories.retain(|_, traj| !traj.is_empty());
+
+        complete
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.current_step >= self.total_steps
+    }
+
+    pub fn progress(&self) -> f32 {
+        if self.total_steps == 0 {
+            return 1.;
+        }
+
+        self.current_step as f32 / self.total_steps as f32
+    }
+
+    pub fn trajectories(&self) -> &HashMap<usize, Trajectory> {
+        &self.trajectories
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct PredictionInput {
+    generation: u64,
+    step_count: u64,
+    grav_const: f32,
+    timestep: f32,
+    auto_recenter: bool,
+    frame_rotation: Option<Vec3>,
+    frame: Option<RotatingFrame>,
+    reference: Option<usize>,
+    maneuvers: Vec<ManeuverNode>,
+}
+
+impl PredictionInput {
+    fn new(
+        objects: &ObjectPool,
+        physics_handler: &PhysicsHandler,
+        frame: Option<RotatingFrame>,
+        reference: Option<usize>,
+    ) -> Self {
+        PredictionInput {
+            generation: objects.generation(),
+            step_count: physics_handler.get_step_count(),
+            grav_const: physics_handler.get_grav_const(),
+            timestep: physics_handler.get_timestep(),
+            auto_recenter: physics_handler.get_auto_recenter(),
+            frame_rotation: physics_handler.get_frame_rotation(),
+            frame,
+            reference,
+            maneuvers: physics_handler.get_maneuvers().to_vec(),
+        }
+    }
+
+    fn offset(
+        &self,
+        objects: &ObjectPool,
+        physics_handler: &PhysicsHandler,
+        frame: Option<RotatingFrame>,
+        reference: Option<usize>,
+    ) -> Option<u64> {
+        let unchanged = self.generation == objects.generation()
+            && self.grav_const == physics_handler.get_grav_const()
+            && self.timestep == physics_handler.get_timestep()
+            && self.auto_recenter == physics_handler.get_auto_recenter()
+            && self.frame_rotation == physics_handler.get_frame_rotation()
+            && self.frame == frame
+            && self.reference == reference
+            && self.maneuvers == physics_handler.get_maneuvers();
+
+        physics_handler
+            .get_step_count()
+            .checked_sub(self.step_count)
+            .filter(|_| unchanged)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct Worker {
+    jobs: Sender<(PredictionInput, TrajectoryIntegration)>,
+    results: Receiver<(PredictionInput, TrajectoryIntegration)>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Worker {
+    fn spawn() -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<(PredictionInput, TrajectoryIntegration)>();
+        let (result_sender, results) = mpsc::channel();
+
+        thread::spawn(move || {
+            for (input, mut job) in job_receiver {
+                job.advance(job.total_steps);
+                if result_sender.send((input, job)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Worker { jobs, results }
+    }
+}
+
+pub struct TrajectoryPredictor {
+    point_count: u32,
+    step: u32,
+    budget: Duration,
+    background: bool,
+    sampling: Option<AdaptiveSampling>,
+    published: HashMap<usize, Trajectory>,
+    published_input: Option<PredictionInput>,
+    pending_input: Option<PredictionInput>,
+    integration: Option<TrajectoryIntegration>,
+    finished: Option<TrajectoryIntegration>,
+    #[cfg(not(target_arch = "wasm32"))]
+    worker: Option<Worker>,
+}
+
+impl TrajectoryPredictor {
+    pub fn new(point_count: u32, step: u32) -> Self {
+        TrajectoryPredictor {
+            point_count,
+            step,
+            budget: Duration::from_millis(4),
+            background: false,
+            sampling: None,
+            published: HashMap::new(),
+            published_input: None,
+            pending_input: None,
+            integration: None,
+            finished: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            worker: None,
+        }
+    }
+
+    pub fn with_budget(mut self, budget: Duration) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn with_background(mut self, background: bool) -> Self {
+        self.background = background && cfg!(not(target_arch = "wasm32"));
+        self
+    }
+
+    pub fn is_background(&self) -> bool {
+        self.background
+    }
+
+    pub fn with_sampling(mut self, sampling: Option<AdaptiveSampling>) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    pub fn update(
+        &mut self,
+        objects: &ObjectPool,
+        physics_handler: &PhysicsHandler,
+        frame: Option<&RotatingFrame>,
+        reference: Option<usize>,
+    ) -> &HashMap<usize, Trajectory> {
+        if self.is_idle() {
+            let offset = self.published_input.as_ref().and_then(|input| {
+                input.offset(objects, physics_handler, frame.copied(), reference)
+            });
+            match offset {
+                Some(0) => {}
+                Some(offset) if offset <= MAX_EXTENSION && self.finished.is_some() => {
+                    self.extend(objects, physics_handler, frame.copied(), reference, offset)
+                }
+                _ => self.start(objects, physics_handler, frame.copied(), reference),
+            }
+        }
+
+        self.poll();
+        self.trajectories()
+    }
+
+    pub fn trajectories(&self) -> &HashMap<usize, Trajectory> {
+        match &self.integration {
+            Some(integration) if self.published_input.is_none() => integration.trajectories(),
+            _ => &self.published,
+        }
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Trajectory> {
+        self.trajectories().get(&id)
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.pending_input.is_none()
+    }
+
+    pub fn is_current(
+        &self,
+        objects: &ObjectPool,
+        physics_handler: &PhysicsHandler,
+        frame: Option<&RotatingFrame>,
+        reference: Option<usize>,
+    ) -> bool {
+        self.is_idle()
+            && self.published_input.as_ref().is_some_and(|input| {
+                input.offset(objects, physics_handler, frame.copied(), reference) == Some(0)
+            })
+    }
+
+    pub fn clear(&mut self) {
+        self.published.clear();
+        self.published_input = None;
+        self.pending_input = None;
+        self.integration = None;
+        self.finished = None;
+    }
+
+    fn start(
+        &mut self,
+        objects: &ObjectPool,
+        physics_handler: &PhysicsHandler,
+        frame: Option<RotatingFrame>,
+        reference: Option<usize>,
+    ) {
+        let integration = TrajectoryIntegration::new(
+            objects,
+            physics_handler,
+            self.point_count,
+            self.step,
+            frame,
+            reference,
+        )
+        .with_sampling(self.sampling);
+        let input = PredictionInput::new(objects, physics_handler, frame, reference);
+        self.pending_input = Some(input.clone());
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.background {
+            let worker = self.worker.get_or_insert_with(Worker::spawn);
+            if worker.jobs.send((input, integration)).is_err() {
+                self.worker = None;
+                self.pending_input = None;
+            }
+            return;
+        }
+
+        self.integration = Some(integration);
+    }
+
+    fn extend(
+        &mut self,
+        objects: &ObjectPool,
+        physics_handler: &PhysicsHandler,
+        frame: Option<RotatingFrame>,
+        reference: Option<usize>,
+        offset: u64,
+    ) {
+        let Some(mut integration) = self.finished.take() else {
+            return;
+        };
+
+        integration.trajectories = std::mem::take(&mut self.published);
+        integration.extend(offset as u32, physics_handler.get_sim_time());
+        self.pending_input = Some(PredictionInput::new(
+            objects,
+            physics_handler,
+            frame,
+            reference,
+        ));
+        self.publish(integration);
+    }
+
+    fn poll(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(worker) = &self.worker
+            && let Ok((input, integration)) = worker.results.try_recv()
+            && self.pending_input.as_ref() == Some(&input)
+        {
+            self.publish(integration);
+        }
+
+        if let Some(integration) = &mut self.integration
+            && integration.advance_for(self.budget)
+            && let Some(integration) = self.integration.take()
+        {
+            self.publish(integration);
+        }
+    }
+
+    fn publish(&mut self, mut integration: TrajectoryIntegration) {
+        self.published = std::mem::take(&mut integration.trajectories);
+        self.published_input = self.pending_input.take();
+        self.finished = Some(integration);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_secs_f64()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now() -> f64 {
+    get_time()
+}
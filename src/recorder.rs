@@ -0,0 +1,160 @@
+use crate::object::ObjectPool;
+use crate::physics::PhysicsHandler;
+use crate::units::{Quantity, Units};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+pub const CSV_HEADER: &str = "time,id,x,y,z,vx,vy,vz,mass,radius";
+
+pub struct Recorder {
+    dir: PathBuf,
+    prefix: String,
+    sample_interval: u64,
+    max_rows_per_file: usize,
+    file_index: usize,
+    rows_in_file: usize,
+    writer: Option<BufWriter<File>>,
+    units: Units,
+}
+
+impl Recorder {
+    pub fn new(
+        dir: impl Into<PathBuf>,
+        prefix: impl Into<String>,
+        sample_interval: u64,
+        max_rows_per_file: usize,
+    ) -> Self {
+        Recorder {
+            dir: dir.into(),
+            prefix: prefix.into(),
+            sample_interval: sample_interval.max(1),
+            max_rows_per_file: max_rows_per_file.max(1),
+            file_index: 0,
+            rows_in_file: 0,
+            writer: None,
+            units: Units::default(),
+        }
+    }
+
+    pub fn with_units(mut self, units: Units) -> Self {
+        self.units = units;
+        self
+    }
+
+    pub fn header(&self) -> String {
+        if !self.units.is_physical() {
+            return CSV_HEADER.to_string();
+        }
+
+        let quantities = [
+            Quantity::Time,
+            Quantity::Time,
+            Quantity::Length,
+            Quantity::Length,
+            Quantity::Length,
+            Quantity::Velocity,
+            Quantity::Velocity,
+            Quantity::Velocity,
+            Quantity::Mass,
+            Quantity::Length,
+        ];
+
+        CSV_HEADER
+            .split(',')
+            .zip(quantities)
+            .map(|(column, quantity)| match column {
+                "id" => column.to_string(),
+                _ => format!("{column} [{}]", self.units.label(quantity)),
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn sample(
+        &mut self,
+        physics_handler: &PhysicsHandler,
+        objects: &ObjectPool,
+    ) -> io::Result<()> {
+        if !physics_handler
+            .get_step_count()
+            .is_multiple_of(self.sample_interval)
+        {
+            return Ok(());
+        }
+
+        self.record(physics_handler.get_sim_time(), objects)
+    }
+
+    pub fn record(&mut self, time: f32, objects: &ObjectPool) -> io::Result<()> {
+        for obj in objects.iter() {
+            if self.writer.is_none() || self.rows_in_file >= self.max_rows_per_file {
+                self.rotate()?;
+            }
+
+            let units = self.units;
+            let length = |value| units.to_display(Quantity::Length, value);
+            let veloc = |value| units.to_display(Quantity::Velocity, value);
+
+            let writer = self.writer.as_mut().unwrap();
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{},{}",
+                units.to_display(Quantity::Time, time),
+                obj.id,
+                length(obj.position.x),
+                length(obj.position.y),
+                length(obj.position.z),
+                veloc(obj.velocity.x),
+                veloc(obj.velocity.y),
+                veloc(obj.velocity.z),
+                units.to_display(Quantity::Mass, obj.mass),
+                length(obj.radius)
+            )?;
+            self.rows_in_file += 1;
+        }
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.writer {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn current_path(&self) -> PathBuf {
+        self.path_for(self.file_index)
+    }
+
+    fn path_for(&self, index: usize) -> PathBuf {
+        self.dir.join(format!("{}_{:04}.csv", self.prefix, index))
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.flush()?;
+
+        if self.writer.is_some() {
+            self.file_index += 1;
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        while self.current_path().exists() {
+            self.file_index += 1;
+        }
+
+        let mut writer = BufWriter::new(File::create(self.current_path())?);
+        writeln!(writer, "{}", self.header())?;
+
+        self.writer = Some(writer);
+        self.rows_in_file = 0;
+        Ok(())
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
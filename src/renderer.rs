@@ -31,15 +31,43 @@ impl Renderer {
         self.obj_mat.set_uniform("world_pos", pos);
     }
 
+    pub fn set_orientation(&self, orientation: Quat) {
+        self.obj_mat
+            .set_uniform("orientation", Mat4::from_quat(orientation.inverse()));
+    }
+
     pub fn move_cam(&mut self, translation: Vec3) {
         self.cam.position += translation.with_x(-translation.x);
         self.cam.target += translation.with_x(-translation.x);
     }
 
+    pub fn track_cam(&mut self, target: Vec3) {
+        let offset = target - self.cam.target;
+        self.cam.position += offset;
+        self.cam.target += offset;
+    }
+
     pub fn get_cam(&self) -> &Camera3D {
         &self.cam
     }
 
+    pub fn get_cam_mut(&mut self) -> &mut Camera3D {
+        &mut self.cam
+    }
+
+    pub fn world_to_screen(&self, pos: Vec3) -> Option<Vec2> {
+        let clip = self.cam.matrix() * pos.extend(1.);
+        if clip.w <= 0. {
+            return None;
+        }
+
+        let ndc = clip.xyz() / clip.w;
+        Some(vec2(
+            (ndc.x + 1.) / 2. * screen_width(),
+            (1. - ndc.y) / 2. * screen_height(),
+        ))
+    }
+
     pub fn begin_drawing(&self) {
         set_camera(&self.cam);
 
@@ -67,11 +95,22 @@ impl Renderer {
     }
 
     pub fn draw_sphere(&self, pos: Vec3, radius: f32, color: Option<Color>) {
+        self.draw_rotated_sphere(pos, radius, Quat::IDENTITY, color);
+    }
+
+    pub fn draw_rotated_sphere(
+        &self,
+        pos: Vec3,
+        radius: f32,
+        orientation: Quat,
+        color: Option<Color>,
+    ) {
         let color = color.unwrap_or(WHITE);
         gl_use_material(&self.obj_mat);
 
         self.set_color(color);
         self.set_world_pos(pos);
+        self.set_orientation(orientation);
 
         draw_sphere(pos, radius, None, color);
         gl_use_default_material();
@@ -83,6 +122,12 @@ impl Renderer {
         draw_sphere(pos, radius, None, color);
     }
 
+    pub fn draw_point(&self, pos: Vec3, size: f32, color: Option<Color>) {
+        let color = color.unwrap_or(WHITE);
+        gl_use_default_material();
+        draw_cube(pos, Vec3::splat(size), None, color);
+    }
+
     pub fn draw_line(&self, start: Vec3, end: Vec3, color: Option<Color>) {
         let color = color.unwrap_or(WHITE);
         draw_line_3d(start, end, color);
@@ -123,6 +168,7 @@ impl Default for Renderer {
                 UniformDesc::new("ambient_light", UniformType::Float1),
                 UniformDesc::new("color", UniformType::Float4),
                 UniformDesc::new("world_pos", UniformType::Float3),
+                UniformDesc::new("orientation", UniformType::Mat4),
             ],
             ..Default::default()
         };
@@ -137,13 +183,16 @@ attribute vec3 position;
 uniform mat4 Model;
 uniform mat4 Projection;
 uniform vec3 world_pos;
+uniform mat4 orientation;
 varying vec3 v_normal;
+varying vec3 v_local_normal;
 
 void main() {
     vec4 world_space = Model * vec4(position, 1.0);
     gl_Position = Projection * world_space;
 
     v_normal = normalize(world_space.xyz - world_pos);
+    v_local_normal = (orientation * vec4(v_normal, 0.0)).xyz;
 }";
 
 const DEFAULT_FRAG_SHADER: &str = "#version 100
@@ -152,12 +201,14 @@ uniform vec3 light_pos;
 uniform vec4 color;
 uniform float ambient_light;
 varying vec3 v_normal;
+varying vec3 v_local_normal;
 
 void main() {
     vec3 lightDir = normalize(light_pos);
 
     float diff = max(dot(v_normal, lightDir), 0.0);
-    vec4 diffuse = min(diff + ambient_light, 1.0) * color;
+    float band = step(0.0, sin(atan(v_local_normal.z, v_local_normal.x) * 4.0)) * 0.15;
+    vec4 diffuse = min(diff + ambient_light, 1.0) * (1.0 - band) * color;
 
     gl_FragColor = diffuse;
 }";
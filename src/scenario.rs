@@ -0,0 +1,282 @@
+use crate::object::{Object, ObjectPool};
+use macroquad::prelude::*;
+use std::f32::consts::TAU;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scenario {
+    Default,
+    SolarSystem,
+    BinaryStar,
+    FigureEight,
+    PlummerSphere,
+    DiskGalaxy,
+    CollidingClusters,
+}
+
+impl Scenario {
+    pub const ALL: [Scenario; 7] = [
+        Scenario::Default,
+        Scenario::SolarSystem,
+        Scenario::BinaryStar,
+        Scenario::FigureEight,
+        Scenario::PlummerSphere,
+        Scenario::DiskGalaxy,
+        Scenario::CollidingClusters,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scenario::Default => "default",
+            Scenario::SolarSystem => "solar-system",
+            Scenario::BinaryStar => "binary-star",
+            Scenario::FigureEight => "figure-eight",
+            Scenario::PlummerSphere => "plummer-sphere",
+            Scenario::DiskGalaxy => "disk-galaxy",
+            Scenario::CollidingClusters => "colliding-clusters",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Scenario> {
+        Self::ALL
+            .into_iter()
+            .find(|scenario| scenario.name() == name)
+    }
+
+    pub fn generate(&self, grav_const: f32) -> ObjectPool {
+        match self {
+            Scenario::Default => {
+                let mut objects = ObjectPool::new();
+                objects.push(Object::default());
+                objects
+            }
+            Scenario::SolarSystem => solar_system(grav_const, 100., 6, 15., 1.5),
+            Scenario::BinaryStar => binary_star(grav_const, 50., 10., 3),
+            Scenario::FigureEight => figure_eight(grav_const, 5., 20.),
+            Scenario::PlummerSphere => plummer_sphere(grav_const, 150, 150., 30.),
+            Scenario::DiskGalaxy => disk_galaxy(grav_const, 200, 300., 80.),
+            Scenario::CollidingClusters => colliding_clusters(grav_const, 80, 80., 20., 150., 1.),
+        }
+    }
+}
+
+pub fn solar_system(
+    grav_const: f32,
+    star_mass: f32,
+    planet_count: usize,
+    inner_radius: f32,
+    spacing: f32,
+) -> ObjectPool {
+    let mut objects = ObjectPool::new();
+    objects.push(body(Vec3::ZERO, Vec3::ZERO, star_mass, YELLOW));
+
+    let mut orbit_radius = inner_radius;
+    for _ in 0..planet_count {
+        let mass = rand::gen_range(0.1, 1.5);
+        let (position, velocity) = circular_orbit(
+            grav_const,
+            star_mass,
+            orbit_radius,
+            rand::gen_range(0., TAU),
+        );
+
+        objects.push(body(position, velocity, mass, random_color()));
+        orbit_radius *= spacing;
+    }
+
+    objects.to_barycentric_frame();
+    objects
+}
+
+pub fn binary_star(
+    grav_const: f32,
+    star_mass: f32,
+    separation: f32,
+    planet_count: usize,
+) -> ObjectPool {
+    let mut objects = ObjectPool::new();
+
+    let star_speed = (grav_const * star_mass / (2. * separation)).sqrt();
+    let offset = vec3(separation / 2., 0., 0.);
+    let veloc = vec3(0., 0., star_speed);
+
+    objects.push(body(offset, veloc, star_mass, YELLOW));
+    objects.push(body(-offset, -veloc, star_mass, ORANGE));
+
+    let mut orbit_radius = separation * 3.;
+    for _ in 0..planet_count {
+        let (position, velocity) = circular_orbit(
+            grav_const,
+            2. * star_mass,
+            orbit_radius,
+            rand::gen_range(0., TAU),
+        );
+
+        objects.push(body(
+            position,
+            velocity,
+            rand::gen_range(0.1, 1.),
+            random_color(),
+        ));
+        orbit_radius *= 1.6;
+    }
+
+    objects.to_barycentric_frame();
+    objects
+}
+
+pub fn figure_eight(grav_const: f32, mass: f32, scale: f32) -> ObjectPool {
+    let position = vec3(0.970_004_4, 0., -0.243_087_5);
+    let velocity = vec3(-0.932_407_4, 0., -0.864_731_5);
+    let speed_scale = (grav_const * mass / scale).sqrt();
+
+    let mut objects = ObjectPool::new();
+    objects.push(body(
+        -position * scale,
+        -velocity / 2. * speed_scale,
+        mass,
+        RED,
+    ));
+    objects.push(body(
+        position * scale,
+        -velocity / 2. * speed_scale,
+        mass,
+        GREEN,
+    ));
+    objects.push(body(Vec3::ZERO, velocity * speed_scale, mass, BLUE));
+
+    objects
+}
+
+pub fn plummer_sphere(
+    grav_const: f32,
+    count: usize,
+    total_mass: f32,
+    scale_radius: f32,
+) -> ObjectPool {
+    let mut objects = ObjectPool::new();
+    let mass = total_mass / count as f32;
+    let color = random_color();
+
+    for _ in 0..count {
+        let (position, velocity) = plummer_sample(grav_const, total_mass, scale_radius);
+        objects.push(body(position, velocity, mass, color));
+    }
+
+    objects.to_barycentric_frame();
+    objects
+}
+
+pub fn disk_galaxy(grav_const: f32, count: usize, core_mass: f32, radius: f32) -> ObjectPool {
+    let mut objects = ObjectPool::new();
+    objects.push(body(Vec3::ZERO, Vec3::ZERO, core_mass, WHITE));
+
+    let star_mass = core_mass * 0.002;
+    let mut radii: Vec<f32> = (0..count)
+        .map(|_| radius * (0.1 + 0.9 * rand::gen_range(0f32, 1.).sqrt()))
+        .collect();
+    radii.sort_by(f32::total_cmp);
+
+    for (i, orbit_radius) in radii.into_iter().enumerate() {
+        let enclosed_mass = core_mass + star_mass * i as f32;
+        let (position, velocity) = circular_orbit(
+            grav_const,
+            enclosed_mass,
+            orbit_radius,
+            rand::gen_range(0., TAU),
+        );
+        let position = position.with_y(rand::gen_range(-1., 1.));
+
+        objects.push(body(position, velocity, star_mass, SKYBLUE));
+    }
+
+    objects.to_barycentric_frame();
+    objects
+}
+
+pub fn colliding_clusters(
+    grav_const: f32,
+    count_per_cluster: usize,
+    cluster_mass: f32,
+    scale_radius: f32,
+    separation: f32,
+    approach_speed: f32,
+) -> ObjectPool {
+    let mut objects = ObjectPool::new();
+    let mass = cluster_mass / count_per_cluster as f32;
+    let offset = vec3(separation / 2., 0., separation / 8.);
+    let boost = vec3(approach_speed / 2., 0., 0.);
+
+    for (side, color) in [(1., PINK), (-1., SKYBLUE)] {
+        for _ in 0..count_per_cluster {
+            let (position, velocity) = plummer_sample(grav_const, cluster_mass, scale_radius);
+            objects.push(body(
+                position + offset * side,
+                velocity - boost * side,
+                mass,
+                color,
+            ));
+        }
+    }
+
+    objects.to_barycentric_frame();
+    objects
+}
+
+pub fn circular_orbit(grav_const: f32, central_mass: f32, radius: f32, angle: f32) -> (Vec3, Vec3) {
+    let dir = vec3(angle.cos(), 0., angle.sin());
+    let tangent = vec3(-angle.sin(), 0., angle.cos());
+    let speed = (grav_const * central_mass / radius).sqrt();
+
+    (dir * radius, tangent * speed)
+}
+
+pub fn radius_for_mass(mass: f32) -> f32 {
+    mass.cbrt().max(0.2)
+}
+
+fn body(position: Vec3, velocity: Vec3, mass: f32, color: Color) -> Object {
+    Object::new(position, velocity, mass, radius_for_mass(mass), color)
+}
+
+fn plummer_sample(grav_const: f32, total_mass: f32, scale_radius: f32) -> (Vec3, Vec3) {
+    let radius = loop {
+        let u: f32 = rand::gen_range(0.01, 1.);
+        let r = scale_radius / (u.powf(-2. / 3.) - 1.).sqrt();
+        if r.is_finite() && r < scale_radius * 10. {
+            break r;
+        }
+    };
+
+    let q = loop {
+        let x: f32 = rand::gen_range(0., 1.);
+        let y: f32 = rand::gen_range(0., 0.1);
+        if y < x * x * (1. - x * x).powf(3.5) {
+            break x;
+        }
+    };
+
+    let escape_speed = (2. * grav_const * total_mass / scale_radius).sqrt()
+        * (1. + radius * radius / (scale_radius * scale_radius)).powf(-0.25);
+
+    (
+        random_direction() * radius,
+        random_direction() * q * escape_speed,
+    )
+}
+
+pub fn random_direction() -> Vec3 {
+    let z: f32 = rand::gen_range(-1., 1.);
+    let angle: f32 = rand::gen_range(0., TAU);
+    let r = (1. - z * z).sqrt();
+
+    vec3(r * angle.cos(), r * angle.sin(), z)
+}
+
+pub fn random_color() -> Color {
+    Color {
+        r: rand::gen_range(0.0, 1.0),
+        g: rand::gen_range(0.0, 1.0),
+        b: rand::gen_range(0.0, 1.0),
+        a: 1.0,
+    }
+}
@@ -0,0 +1,230 @@
+use crate::object::{Object, ObjectPool};
+use crate::physics::PhysicsHandler;
+use crate::renderer::Renderer;
+use crate::units::{UnitSystem, Units};
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    Parse(String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(err) => write!(f, "scene io error: {err}"),
+            SceneError::Parse(err) => write!(f, "scene parse error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<std::io::Error> for SceneError {
+    fn from(err: std::io::Error) -> Self {
+        SceneError::Io(err)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SceneObject {
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+    pub mass: f32,
+    pub radius: f32,
+    pub color: [f32; 4],
+    #[serde(default)]
+    pub spin: [f32; 3],
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub test_particle: bool,
+}
+
+impl From<&Object> for SceneObject {
+    fn from(obj: &Object) -> Self {
+        SceneObject {
+            position: obj.position.to_array(),
+            velocity: obj.velocity.to_array(),
+            mass: obj.mass,
+            radius: obj.radius,
+            color: obj.color.into(),
+            spin: obj.spin.to_array(),
+            name: obj.name.clone(),
+            test_particle: obj.test_particle,
+        }
+    }
+}
+
+impl From<&SceneObject> for Object {
+    fn from(obj: &SceneObject) -> Self {
+        let mut object = Object::new(
+            Vec3::from_array(obj.position),
+            Vec3::from_array(obj.velocity),
+            obj.mass,
+            obj.radius,
+            obj.color.into(),
+        );
+        object.spin = Vec3::from_array(obj.spin);
+        object.name = obj.name.clone();
+        object.test_particle = obj.test_particle;
+        object
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SceneCamera {
+    pub position: [f32; 3],
+    pub target: [f32; 3],
+    pub up: [f32; 3],
+    pub fovy: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SceneUnitSystem {
+    Natural,
+    Si,
+    Astronomical,
+}
+
+impl From<UnitSystem> for SceneUnitSystem {
+    fn from(system: UnitSystem) -> Self {
+        match system {
+            UnitSystem::Natural => SceneUnitSystem::Natural,
+            UnitSystem::Si => SceneUnitSystem::Si,
+            UnitSystem::Astronomical => SceneUnitSystem::Astronomical,
+        }
+    }
+}
+
+impl From<SceneUnitSystem> for UnitSystem {
+    fn from(system: SceneUnitSystem) -> Self {
+        match system {
+            SceneUnitSystem::Natural => UnitSystem::Natural,
+            SceneUnitSystem::Si => UnitSystem::Si,
+            SceneUnitSystem::Astronomical => UnitSystem::Astronomical,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SceneUnits {
+    pub system: SceneUnitSystem,
+    pub length: f64,
+    pub mass: f64,
+    pub time: f64,
+}
+
+impl From<&Units> for SceneUnits {
+    fn from(units: &Units) -> Self {
+        SceneUnits {
+            system: SceneUnitSystem::from(units.system),
+            length: units.length,
+            mass: units.mass,
+            time: units.time,
+        }
+    }
+}
+
+impl From<&SceneUnits> for Units {
+    fn from(units: &SceneUnits) -> Self {
+        Units {
+            system: UnitSystem::from(units.system),
+            length: units.length,
+            mass: units.mass,
+            time: units.time,
+        }
+    }
+}
+
+impl Default for SceneUnits {
+    fn default() -> Self {
+        SceneUnits::from(&Units::natural())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    pub grav_const: f32,
+    pub timestep: f32,
+    #[serde(default)]
+    pub units: SceneUnits,
+    pub camera: SceneCamera,
+    pub objects: Vec<SceneObject>,
+}
+
+impl Scene {
+    pub fn capture(
+        objects: &ObjectPool,
+        physics_handler: &PhysicsHandler,
+        renderer: &Renderer,
+        units: &Units,
+    ) -> Self {
+        let cam = renderer.get_cam();
+
+        Scene {
+            grav_const: physics_handler.get_grav_const(),
+            timestep: physics_handler.get_timestep(),
+            units: SceneUnits::from(units),
+            camera: SceneCamera {
+                position: cam.position.to_array(),
+                target: cam.target.to_array(),
+                up: cam.up.to_array(),
+                fovy: cam.fovy,
+            },
+            objects: objects.iter().map(SceneObject::from).collect(),
+        }
+    }
+
+    pub fn apply(
+        &self,
+        objects: &mut ObjectPool,
+        physics_handler: &mut PhysicsHandler,
+        renderer: &mut Renderer,
+        units: &mut Units,
+    ) {
+        *objects = self.build_pool();
+        *units = Units::from(&self.units);
+
+        physics_handler.set_grav_const(self.grav_const);
+        physics_handler.set_timestep(self.timestep);
+
+        let cam = renderer.get_cam_mut();
+        cam.position = Vec3::from_array(self.camera.position);
+        cam.target = Vec3::from_array(self.camera.target);
+        cam.up = Vec3::from_array(self.camera.up);
+        cam.fovy = self.camera.fovy;
+    }
+
+    pub fn build_pool(&self) -> ObjectPool {
+        let mut pool = ObjectPool::new();
+        for obj in &self.objects {
+            pool.push(Object::from(obj));
+        }
+
+        pool
+    }
+
+    pub fn to_ron(&self) -> Result<String, SceneError> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| SceneError::Parse(err.to_string()))
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, SceneError> {
+        ron::from_str(text).map_err(|err| SceneError::Parse(err.to_string()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SceneError> {
+        fs::write(path, self.to_ron()?)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+}
@@ -0,0 +1,204 @@
+use crate::object::Object;
+use crate::scenario::{radius_for_mass, random_direction};
+use macroquad::prelude::*;
+use std::f32::consts::TAU;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpawnShape {
+    Ring,
+    Disk,
+    Shell,
+    Cloud,
+}
+
+impl SpawnShape {
+    pub const ALL: [SpawnShape; 4] = [
+        SpawnShape::Ring,
+        SpawnShape::Disk,
+        SpawnShape::Shell,
+        SpawnShape::Cloud,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpawnShape::Ring => "Ring",
+            SpawnShape::Disk => "Disk",
+            SpawnShape::Shell => "Spherical Shell",
+            SpawnShape::Cloud => "Random Cloud",
+        }
+    }
+
+    pub fn next(&self) -> SpawnShape {
+        match self {
+            SpawnShape::Ring => SpawnShape::Disk,
+            SpawnShape::Disk => SpawnShape::Shell,
+            SpawnShape::Shell => SpawnShape::Cloud,
+            SpawnShape::Cloud => SpawnShape::Ring,
+        }
+    }
+
+    fn is_planar(&self) -> bool {
+        matches!(self, SpawnShape::Ring | SpawnShape::Disk)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VelocityProfile {
+    Keplerian,
+    Static,
+    Random,
+}
+
+impl VelocityProfile {
+    pub fn name(&self) -> &'static str {
+        match self {
+            VelocityProfile::Keplerian => "Keplerian",
+            VelocityProfile::Static => "Static",
+            VelocityProfile::Random => "Random",
+        }
+    }
+
+    pub fn next(&self) -> VelocityProfile {
+        match self {
+            VelocityProfile::Keplerian => VelocityProfile::Static,
+            VelocityProfile::Static => VelocityProfile::Random,
+            VelocityProfile::Random => VelocityProfile::Keplerian,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpawnTool {
+    pub shape: SpawnShape,
+    pub count: usize,
+    pub radius_range: (f32, f32),
+    pub mass_range: (f32, f32),
+    pub velocity: VelocityProfile,
+    pub color: Color,
+}
+
+impl SpawnTool {
+    pub fn new(shape: SpawnShape, count: usize) -> Self {
+        SpawnTool {
+            shape,
+            count,
+            radius_range: (20., 40.),
+            mass_range: (0., 0.),
+            velocity: VelocityProfile::Keplerian,
+            color: WHITE,
+        }
+    }
+
+    pub fn with_radius_range(mut self, min: f32, max: f32) -> Self {
+        self.radius_range = (min.min(max), min.max(max));
+        self
+    }
+
+    pub fn with_mass_range(mut self, min: f32, max: f32) -> Self {
+        self.mass_range = (min.min(max), min.max(max));
+        self
+    }
+
+    pub fn with_velocity(mut self, velocity: VelocityProfile) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn spawns_particles(&self) -> bool {
+        self.mass_range.1 <= 0.
+    }
+
+    pub fn generate(&self, center: &Object, grav_const: f32) -> Vec<Object> {
+        (0..self.count)
+            .map(|i| {
+                let offset = self.sample_offset(i);
+                let velocity = self.sample_velocity(offset, center.mass, grav_const);
+                let mass = self.sample_mass();
+
+                if mass <= 0. {
+                    Object::particle(
+                        center.position + offset,
+                        center.velocity + velocity,
+                        self.color,
+                    )
+                } else {
+                    Object::new(
+                        center.position + offset,
+                        center.velocity + velocity,
+                        mass,
+                        radius_for_mass(mass),
+                        self.color,
+                    )
+                }
+            })
+            .collect()
+    }
+
+    fn sample_offset(&self, index: usize) -> Vec3 {
+        let (min, max) = self.radius_range;
+
+        match self.shape {
+            SpawnShape::Ring => {
+                let angle = index as f32 / self.count as f32 * TAU;
+                vec3(angle.cos(), 0., angle.sin()) * rand::gen_range(min, max)
+            }
+            SpawnShape::Disk => {
+                let angle: f32 = rand::gen_range(0., TAU);
+                let radius = rand::gen_range(min * min, max * max).sqrt();
+                vec3(angle.cos(), 0., angle.sin()) * radius
+            }
+            SpawnShape::Shell => random_direction() * rand::gen_range(min, max),
+            SpawnShape::Cloud => {
+                let radius = rand::gen_range(min.powi(3), max.powi(3)).cbrt();
+                random_direction() * radius
+            }
+        }
+    }
+
+    fn sample_velocity(&self, offset: Vec3, central_mass: f32, grav_const: f32) -> Vec3 {
+        let distance = offset.length();
+        if distance == 0. {
+            return Vec3::ZERO;
+        }
+
+        let speed = (grav_const * central_mass / distance).sqrt();
+
+        match self.velocity {
+            VelocityProfile::Keplerian => self.tangent(offset) * speed,
+            VelocityProfile::Static => Vec3::ZERO,
+            VelocityProfile::Random => random_direction() * rand::gen_range(0., speed),
+        }
+    }
+
+    fn tangent(&self, offset: Vec3) -> Vec3 {
+        let axis = if self.shape.is_planar() {
+            Vec3::NEG_Y
+        } else {
+            random_direction()
+        };
+
+        axis.cross(offset)
+            .try_normalize()
+            .unwrap_or_else(|| offset.any_orthonormal_vector())
+    }
+
+    fn sample_mass(&self) -> f32 {
+        let (min, max) = self.mass_range;
+        if max <= 0. {
+            return 0.;
+        }
+
+        rand::gen_range(min, max)
+    }
+}
+
+impl Default for SpawnTool {
+    fn default() -> Self {
+        SpawnTool::new(SpawnShape::Ring, 64)
+    }
+}
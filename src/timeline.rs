@@ -0,0 +1,213 @@
+use crate::object::{Object, ObjectPool, Trajectory};
+use crate::physics::PhysicsHandler;
+use crate::units::{Quantity, Units};
+use macroquad::prelude::*;
+use std::collections::{HashMap, VecDeque};
+
+pub struct Snapshot {
+    pub sim_time: f32,
+    pub step_count: u64,
+    pub objects: ObjectPool,
+}
+
+pub struct Timeline {
+    snapshots: VecDeque<Snapshot>,
+    interval: u64,
+    memory_budget: usize,
+    paused: bool,
+    cursor: Option<usize>,
+}
+
+impl Timeline {
+    pub fn new(interval: u64, memory_budget: usize) -> Self {
+        Timeline {
+            snapshots: VecDeque::new(),
+            interval: interval.max(1),
+            memory_budget,
+            paused: false,
+            cursor: None,
+        }
+    }
+
+    pub fn sample(&mut self, physics_handler: &PhysicsHandler, objects: &ObjectPool) {
+        if !physics_handler
+            .get_step_count()
+            .is_multiple_of(self.interval)
+        {
+            return;
+        }
+
+        self.push(physics_handler, objects);
+    }
+
+    pub fn push(&mut self, physics_handler: &PhysicsHandler, objects: &ObjectPool) {
+        if self
+            .snapshots
+            .back()
+            .is_some_and(|snapshot| snapshot.step_count == physics_handler.get_step_count())
+        {
+            self.snapshots.pop_back();
+        }
+
+        self.snapshots.push_back(Snapshot {
+            sim_time: physics_handler.get_sim_time(),
+            step_count: physics_handler.get_step_count(),
+            objects: objects.clone(),
+        });
+
+        while self.snapshots.len() > 1 && self.memory_usage() > self.memory_budget {
+            self.snapshots.pop_front();
+            self.cursor = self.cursor.map(|cursor| cursor.saturating_sub(1));
+        }
+    }
+
+    pub fn pause(&mut self, physics_handler: &PhysicsHandler, objects: &ObjectPool) {
+        if self.paused {
+            return;
+        }
+
+        self.push(physics_handler, objects);
+        self.paused = true;
+    }
+
+    pub fn resume(
+        &mut self,
+        objects: &mut ObjectPool,
+        physics_handler: &mut PhysicsHandler,
+    ) -> bool {
+        self.paused = false;
+        if self.is_edited(objects) {
+            self.branch(objects);
+        }
+
+        let Some(cursor) = self.cursor.take() else {
+            return false;
+        };
+        let Some(snapshot) = self
+            .snapshots
+            .back()
+            .filter(|_| cursor + 1 < self.snapshots.len())
+        else {
+            return false;
+        };
+        *objects = snapshot.objects.clone();
+        physics_handler.set_clock(snapshot.sim_time, snapshot.step_count);
+        true
+    }
+
+    pub fn scrub(
+        &mut self,
+        offset: isize,
+        objects: &mut ObjectPool,
+        physics_handler: &mut PhysicsHandler,
+    ) -> bool {
+        if !self.paused || self.snapshots.is_empty() {
+            return false;
+        }
+
+        let current = self.cursor.unwrap_or(self.snapshots.len() - 1);
+        let target = current
+            .saturating_add_signed(offset)
+            .min(self.snapshots.len() - 1);
+
+        self.cursor = Some(target);
+
+        let snapshot = &self.snapshots[target];
+        *objects = snapshot.objects.clone();
+        physics_handler.set_clock(snapshot.sim_time, snapshot.step_count);
+        true
+    }
+
+    pub fn branch(&mut self, objects: &ObjectPool) {
+        let Some(cursor) = self.cursor else {
+            return;
+        };
+
+        self.snapshots.truncate(cursor + 1);
+        self.snapshots[cursor].objects = objects.clone();
+    }
+
+    pub fn is_edited(&self, objects: &ObjectPool) -> bool {
+        self.cursor
+            .is_some_and(|cursor| self.snapshots[cursor].objects != *objects)
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.cursor = None;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn cursor(&self) -> Option<usize> {
+        self.cursor
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn trajectories(&self) -> HashMap<usize, Trajectory> {
+        let end = self
+            .cursor
+            .map_or(self.snapshots.len(), |cursor| cursor + 1);
+        let mut trajectories: HashMap<usize, Trajectory> = HashMap::new();
+
+        for snapshot in self.snapshots.iter().take(end) {
+            for obj in snapshot.objects.iter() {
+                trajectories
+                    .entry(obj.id)
+                    .or_default()
+                    .push_at(obj.position, snapshot.sim_time);
+            }
+        }
+
+        trajectories
+    }
+
+    pub fn memory_usage(&self) -> usize {
+        self.snapshots
+            .iter()
+            .map(|snapshot| size_of::<Snapshot>() + snapshot.objects.len() * size_of::<Object>())
+            .sum()
+    }
+
+    pub fn draw(&self, units: &Units, x: f32, y: f32, font_size: f32, color: Color) {
+        if !self.paused {
+            return;
+        }
+
+        let position = self
+            .cursor
+            .unwrap_or(self.snapshots.len().saturating_sub(1));
+        let time = self
+            .snapshots
+            .get(position)
+            .map_or(0., |snapshot| snapshot.sim_time);
+
+        draw_text(
+            &format!(
+                "PAUSED  t = {}  [{}/{}]",
+                units.format(Quantity::Time, time),
+                position + 1,
+                self.snapshots.len()
+            ),
+            x,
+            y,
+            font_size,
+            color,
+        );
+    }
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Timeline::new(5, 64 * 1024 * 1024)
+    }
+}
@@ -0,0 +1,187 @@
+use crate::frame::RotatingFrame;
+use crate::object::ObjectPool;
+use crate::physics::PhysicsHandler;
+use crate::renderer::Renderer;
+use macroquad::prelude::*;
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TrailMode {
+    #[default]
+    All,
+    Selected,
+    Off,
+}
+
+impl TrailMode {
+    pub fn next(&self) -> TrailMode {
+        match self {
+            TrailMode::All => TrailMode::Selected,
+            TrailMode::Selected => TrailMode::Off,
+            TrailMode::Off => TrailMode::All,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Trail {
+    points: VecDeque<Vec3>,
+}
+
+impl Trail {
+    pub fn push(&mut self, point: Vec3, capacity: usize) {
+        while self.points.len() >= capacity.max(1) {
+            self.points.pop_front();
+        }
+
+        self.points.push_back(point);
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Vec3> {
+        self.points.iter()
+    }
+
+    pub fn alpha(&self, index: usize) -> f32 {
+        (index + 1) as f32 / self.points.len().max(1) as f32
+    }
+
+    pub fn draw(&self, renderer: &Renderer, color: Color) {
+        for (i, (start, end)) in self
+            .points
+            .iter()
+            .zip(self.points.iter().skip(1))
+            .enumerate()
+        {
+            renderer.draw_line(
+                *start,
+                *end,
+                Some(Color {
+                    a: color.a * self.alpha(i + 1),
+                    ..color
+                }),
+            );
+        }
+    }
+}
+
+pub struct Trails {
+    trails: HashMap<usize, Trail>,
+    length: usize,
+    interval: u64,
+    mode: TrailMode,
+    frame: Option<RotatingFrame>,
+}
+
+impl Trails {
+    pub fn new(length: usize, interval: u64) -> Self {
+        Trails {
+            trails: HashMap::new(),
+            length: length.max(1),
+            interval: interval.max(1),
+            mode: TrailMode::default(),
+            frame: None,
+        }
+    }
+
+    pub fn sample(
+        &mut self,
+        physics_handler: &PhysicsHandler,
+        objects: &ObjectPool,
+        frame: Option<&RotatingFrame>,
+    ) {
+        if self.mode == TrailMode::Off {
+            return;
+        }
+
+        if self.frame.as_ref() != frame {
+            self.frame = frame.copied();
+            self.trails.clear();
+        }
+
+        if !physics_handler
+            .get_step_count()
+            .is_multiple_of(self.interval)
+        {
+            return;
+        }
+
+        let transform = frame.and_then(|frame| frame.transform(objects));
+        for obj in objects.iter() {
+            let point = transform.map_or(obj.position, |transform| {
+                transform.point_to_frame(obj.position)
+            });
+            self.trails
+                .entry(obj.id)
+                .or_default()
+                .push(point, self.length);
+        }
+
+        self.trails.retain(|id, _| objects.get(*id).is_some());
+    }
+
+    pub fn draw(&self, renderer: &Renderer, objects: &ObjectPool, selected: &[usize]) {
+        for (id, trail) in &self.trails {
+            let visible = match self.mode {
+                TrailMode::All => true,
+                TrailMode::Selected => selected.contains(id),
+                TrailMode::Off => false,
+            };
+
+            if let Some(obj) = objects.get(*id)
+                && visible
+            {
+                trail.draw(renderer, obj.color);
+            }
+        }
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Trail> {
+        self.trails.get(&id)
+    }
+
+    pub fn clear(&mut self) {
+        self.trails.clear();
+    }
+
+    pub fn get_mode(&self) -> TrailMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: TrailMode) {
+        if mode == TrailMode::Off {
+            self.trails.clear();
+        }
+
+        self.mode = mode;
+    }
+
+    pub fn get_length(&self) -> usize {
+        self.length
+    }
+
+    pub fn set_length(&mut self, length: usize) {
+        self.length = length.max(1);
+    }
+
+    pub fn get_interval(&self) -> u64 {
+        self.interval
+    }
+
+    pub fn set_interval(&mut self, interval: u64) {
+        self.interval = interval.max(1);
+    }
+}
+
+impl Default for Trails {
+    fn default() -> Self {
+        Trails::new(200, 5)
+    }
+}
@@ -0,0 +1,173 @@
+pub const GRAV_CONST_SI: f64 = 6.674_30e-11;
+pub const AU_M: f64 = 1.495_978_707e11;
+pub const DAY_S: f64 = 86_400.;
+pub const SOLAR_MASS_KG: f64 = 1.327_124_400_18e20 / GRAV_CONST_SI;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnitSystem {
+    Natural,
+    Si,
+    Astronomical,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Quantity {
+    Length,
+    Mass,
+    Time,
+    Velocity,
+    Energy,
+    Momentum,
+    AngularMomentum,
+}
+
+impl Quantity {
+    fn dimensions(&self) -> (i32, i32, i32) {
+        match self {
+            Quantity::Length => (1, 0, 0),
+            Quantity::Mass => (0, 1, 0),
+            Quantity::Time => (0, 0, 1),
+            Quantity::Velocity => (1, 0, -1),
+            Quantity::Energy => (2, 1, -2),
+            Quantity::Momentum => (1, 1, -1),
+            Quantity::AngularMomentum => (2, 1, -1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Units {
+    pub system: UnitSystem,
+    pub length: f64,
+    pub mass: f64,
+    pub time: f64,
+}
+
+impl Units {
+    pub fn natural() -> Self {
+        Units {
+            system: UnitSystem::Natural,
+            length: 1.,
+            mass: 1.,
+            time: 1.,
+        }
+    }
+
+    pub fn si(length_m: f64, time_s: f64, grav_const: f32) -> Self {
+        Units {
+            system: UnitSystem::Si,
+            length: length_m,
+            mass: Self::mass_for(length_m, time_s, grav_const),
+            time: time_s,
+        }
+    }
+
+    pub fn astronomical(length_per_au: f64, time_per_day: f64, grav_const: f32) -> Self {
+        let length = AU_M / length_per_au;
+        let time = DAY_S / time_per_day;
+
+        Units {
+            system: UnitSystem::Astronomical,
+            length,
+            mass: Self::mass_for(length, time, grav_const),
+            time,
+        }
+    }
+
+    pub fn from_name(name: &str, grav_const: f32) -> Option<Self> {
+        match name {
+            "natural" => Some(Self::natural()),
+            "si" => Some(Self::si(1e9, DAY_S, grav_const)),
+            "astronomical" => Some(Self::astronomical(50., 1., grav_const)),
+            _ => None,
+        }
+    }
+
+    pub fn grav_const(&self) -> f32 {
+        match self.system {
+            UnitSystem::Natural => 1.,
+            _ => (GRAV_CONST_SI * self.mass * self.time * self.time / self.length.powi(3)) as f32,
+        }
+    }
+
+    pub fn is_physical(&self) -> bool {
+        self.system != UnitSystem::Natural
+    }
+
+    pub fn to_display(&self, quantity: Quantity, value: f32) -> f64 {
+        value as f64 * self.display_factor(quantity)
+    }
+
+    pub fn from_display(&self, quantity: Quantity, value: f64) -> f32 {
+        (value / self.display_factor(quantity)) as f32
+    }
+
+    pub fn to_si(&self, quantity: Quantity, value: f32) -> f64 {
+        let (l, m, t) = quantity.dimensions();
+        value as f64 * self.length.powi(l) * self.mass.powi(m) * self.time.powi(t)
+    }
+
+    pub fn from_si(&self, quantity: Quantity, value: f64) -> f32 {
+        let (l, m, t) = quantity.dimensions();
+        (value / (self.length.powi(l) * self.mass.powi(m) * self.time.powi(t))) as f32
+    }
+
+    pub fn label(&self, quantity: Quantity) -> String {
+        let (length, mass, time) = match self.system {
+            UnitSystem::Natural => return String::new(),
+            UnitSystem::Si => {
+                match quantity {
+                    Quantity::Energy => return String::from("J"),
+                    Quantity::Momentum => return String::from("N s"),
+                    _ => {}
+                }
+                ("m", "kg", "s")
+            }
+            UnitSystem::Astronomical => ("AU", "M_sun", "d"),
+        };
+
+        let (l, m, t) = quantity.dimensions();
+        [(mass, m), (length, l), (time, t)]
+            .iter()
+            .filter(|(_, exp)| *exp != 0)
+            .map(|(unit, exp)| match exp {
+                1 => unit.to_string(),
+                _ => format!("{unit}^{exp}"),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn format(&self, quantity: Quantity, value: f32) -> String {
+        if !self.is_physical() {
+            return format!("{value:.3}");
+        }
+
+        format!(
+            "{:.4e} {}",
+            self.to_display(quantity, value),
+            self.label(quantity)
+        )
+    }
+
+    fn display_factor(&self, quantity: Quantity) -> f64 {
+        let (l, m, t) = quantity.dimensions();
+        let (length, mass, time) = match self.system {
+            UnitSystem::Natural => return 1.,
+            UnitSystem::Si => (1., 1., 1.),
+            UnitSystem::Astronomical => (AU_M, SOLAR_MASS_KG, DAY_S),
+        };
+
+        (self.length / length).powi(l) * (self.mass / mass).powi(m) * (self.time / time).powi(t)
+    }
+
+    fn mass_for(length: f64, time: f64, grav_const: f32) -> f64 {
+        grav_const as f64 * length.powi(3) / (GRAV_CONST_SI * time * time)
+    }
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Units::natural()
+    }
+}
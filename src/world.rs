@@ -1,15 +1,30 @@
+use crate::boundary::{BoundaryEvent, BoundaryLog};
 use crate::control::*;
+use crate::influence::{InfluenceTracker, SoiEvent};
 use crate::object::*;
 use crate::physics::*;
+use crate::recorder::Recorder;
 use crate::renderer::*;
+use crate::timeline::Timeline;
+use crate::trail::Trails;
+use crate::units::Units;
 
 pub const SIM_SPEED: f32 = 20.0;
 
+#[derive(Default)]
 pub struct World {
     pub objects: ObjectPool,
     pub physics_handler: PhysicsHandler,
     pub renderer: Renderer,
     pub input_handler: ControlHandler,
+    pub boundary_events: Vec<BoundaryEvent>,
+    pub boundary_log: BoundaryLog,
+    pub influence: InfluenceTracker,
+    pub soi_events: Vec<SoiEvent>,
+    pub recorder: Option<Recorder>,
+    pub timeline: Timeline,
+    pub trails: Trails,
+    pub units: Units,
 }
 
 impl World {
@@ -24,33 +39,87 @@ impl World {
             physics_handler,
             renderer,
             input_handler,
+            boundary_events: Vec::new(),
+            boundary_log: BoundaryLog::default(),
+            influence: InfluenceTracker::default(),
+            soi_events: Vec::new(),
+            recorder: None,
+            timeline: Timeline::default(),
+            trails: Trails::default(),
+            units: Units::default(),
         }
     }
 
     pub fn update(&mut self, dt: f32) {
+        if !self.timeline.is_paused() {
+            self.step_physics(dt * SIM_SPEED);
+        }
+        self.input_handler
+            .track_camera(&mut self.renderer, &self.objects);
         self.renderer.begin_drawing();
 
-        self.physics_handler
-            .update(&mut self.objects, dt * SIM_SPEED);
-        self.objects.draw_all(&self.renderer);
+        self.boundary_events = self.physics_handler.drain_events();
+        self.boundary_log
+            .record(self.physics_handler.get_sim_time(), &self.boundary_events);
+        self.soi_events = self.influence.update(
+            &self.objects,
+            self.physics_handler.get_grav_const(),
+            self.physics_handler.get_sim_time(),
+        );
+        match self.input_handler.get_frame_transform(&self.objects) {
+            Some(transform) => transform
+                .pool_to_frame(&self.objects)
+                .draw_all(&self.renderer),
+            None => self.objects.draw_all(&self.renderer),
+        }
+        let selected = self
+            .input_handler
+            .get_active_group()
+            .map_or(Vec::new(), |group| self.objects.group_ids(group));
+        self.trails.draw(&self.renderer, &self.objects, &selected);
         self.input_handler.handle_input(
             &mut self.renderer,
             &mut self.objects,
-            &self.physics_handler,
+            &mut self.physics_handler,
+            &mut self.timeline,
+            &mut self.units,
             dt,
         );
+        if self.input_handler.take_discontinuity() {
+            self.trails.clear();
+            self.influence.clear();
+        }
+        self.input_handler
+            .draw_hovered_influence(&self.renderer, &self.objects, &self.influence);
+
+        if self.timeline.is_edited(&self.objects) {
+            self.timeline.branch(&self.objects);
+        }
 
         self.renderer.end_drawing();
     }
-}
 
-impl Default for World {
-    fn default() -> Self {
-        World {
-            objects: ObjectPool::default(),
-            physics_handler: PhysicsHandler::default(),
-            renderer: Renderer::default(),
-            input_handler: ControlHandler::default(),
-        }
+    pub fn set_units(&mut self, units: Units) {
+        self.physics_handler.set_grav_const(units.grav_const());
+        self.units = units;
+    }
+
+    pub fn step_physics(&mut self, dt: f32) {
+        let recorder = &mut self.recorder;
+        let timeline = &mut self.timeline;
+        let trails = &mut self.trails;
+        let frame = self.input_handler.get_rotating_frame();
+        self.physics_handler
+            .update_with(&mut self.objects, dt, |physics_handler, objects| {
+                timeline.sample(physics_handler, objects);
+                trails.sample(physics_handler, objects, frame.as_ref());
+
+                if let Some(rec) = recorder
+                    && let Err(err) = rec.sample(physics_handler, objects)
+                {
+                    eprintln!("Failed to record state: {err}");
+                    *recorder = None;
+                }
+            });
     }
 }